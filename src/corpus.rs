@@ -0,0 +1,131 @@
+//! Labeled posts for measuring how well the filter does
+//!
+//! A corpus is a JSONL file with one post per line:
+//!
+//! ```json
+//! {"id": "p1", "title": "Just minted CLAW", "content": "Buy now!", "author": "x", "label": "spam", "category": "crypto"}
+//! ```
+//!
+//! `id`, `author` and `category` are optional.
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::path::Path;
+
+use crate::filter::PostView;
+use crate::jsonl::{append_lines, parse_lines, write_lines};
+use crate::moltbook::Post;
+
+mod authors;
+mod calibrate;
+mod eval;
+mod votes;
+
+pub use authors::{AuthorRecord, AuthorStore, Mark, ALLOW_WEIGHT, DENY_WEIGHT, MAX_LEARNED_WEIGHT};
+pub use calibrate::{Calibration, ThresholdPoint};
+pub use eval::{Confusion, EvalReport, FlagStats, Misclassified};
+pub use votes::{Vote, VoteHistory, VoteRecord};
+
+/// Error raised while reading or writing a corpus, vote history or author store
+pub type CorpusError = crate::DataFileError;
+
+/// The true class of a labeled post
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Label {
+    Spam,
+    Ham,
+}
+
+/// A post with its true label
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Sample {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
+    pub title: String,
+    #[serde(default)]
+    pub content: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub author: Option<String>,
+    pub label: Label,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub category: Option<String>,
+}
+
+impl Sample {
+    /// Label a post fetched from the API
+    pub fn from_post(post: &Post, label: Label, category: Option<String>) -> Self {
+        Self {
+            id: Some(post.id.clone()),
+            title: post.title.clone(),
+            content: post.content.clone(),
+            author: post.author.clone(),
+            label,
+            category,
+        }
+    }
+
+    /// The text a model learns from, and whether it is spam
+    pub fn training_text(&self) -> (String, bool) {
+        let view = PostView::new(&self.title, &self.content, None);
+        (view.full_text().to_string(), self.label == Label::Spam)
+    }
+}
+
+/// Parse a corpus from JSONL source; blank lines are ignored
+pub fn parse(src: &str) -> Result<Vec<Sample>, CorpusError> {
+    parse_lines(src)
+}
+
+/// Read a corpus file
+pub fn load(path: impl AsRef<Path>) -> Result<Vec<Sample>, CorpusError> {
+    let path = path.as_ref();
+    let src = std::fs::read_to_string(path).map_err(|e| CorpusError {
+        line: None,
+        message: format!("failed to read {}: {}", path.display(), e),
+    })?;
+    parse(&src)
+}
+
+/// Post ids already in a corpus, so labeling sessions can pick up where they left off
+pub fn labeled_ids(samples: &[Sample]) -> HashSet<String> {
+    samples.iter().filter_map(|s| s.id.clone()).collect()
+}
+
+/// Append samples to a corpus file, creating it if needed
+pub fn append(path: impl AsRef<Path>, samples: &[Sample]) -> Result<(), CorpusError> {
+    append_lines(path.as_ref(), samples)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_corpus() {
+        let src = "{\"title\": \"Hi\", \"content\": \"Hello\", \"label\": \"ham\"}\n\n\
+                   {\"id\": \"p2\", \"title\": \"CLAW\", \"label\": \"spam\", \"category\": \"crypto\"}\n";
+        let samples = parse(src).unwrap();
+        assert_eq!(samples.len(), 2);
+        assert_eq!(samples[0].label, Label::Ham);
+        assert_eq!(samples[1].id.as_deref(), Some("p2"));
+        assert_eq!(samples[1].content, "");
+    }
+
+    #[test]
+    fn test_parse_error_has_line() {
+        let src = "{\"title\": \"Hi\", \"label\": \"ham\"}\n{\"title\": \"Hi\", \"label\": \"maybe\"}\n";
+        let err = parse(src).unwrap_err();
+        assert_eq!(err.line, Some(2));
+        assert!(err.message.contains("maybe"));
+    }
+
+    #[test]
+    fn test_labeled_ids_skip_unidentified() {
+        let src = "{\"id\": \"p1\", \"title\": \"Hi\", \"label\": \"ham\"}\n\
+                   {\"title\": \"Hi\", \"label\": \"spam\"}\n";
+        let ids = labeled_ids(&parse(src).unwrap());
+        assert_eq!(ids.len(), 1);
+        assert!(ids.contains("p1"));
+    }
+}
@@ -0,0 +1,273 @@
+//! What is known about each author
+//!
+//! The store is a JSONL file with one line per author, rewritten on save:
+//!
+//! ```json
+//! {"name": "Shill", "posts": 12, "spam": 11, "score_total": 140, "mark": "deny", "last_seen": "2026-01-01T00:00:00Z"}
+//! ```
+//!
+//! `feed` adds the posts it scores, and the `authors` commands set manual marks.
+//! A new store starts with the authors the filter used to trust outright
+//! marked allow.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::Path;
+
+use super::{parse_lines, write_lines, CorpusError};
+pub use crate::filter::{ALLOW_WEIGHT, DENY_WEIGHT};
+
+/// Largest change earned from an author's history alone
+pub const MAX_LEARNED_WEIGHT: i32 = 20;
+
+/// Days after which an author's history counts half as much
+const HALF_LIFE_DAYS: f64 = 30.0;
+
+/// Posts it takes for a history to count for half its full weight
+const PRIOR_POSTS: f64 = 5.0;
+
+/// Post ids kept per author, so a post seen on several runs counts once
+const RECENT_IDS: usize = 200;
+
+/// Authors that got a fixed bonus before the store existed
+const FORMER_QUALITY_AUTHORS: [&str; 8] = [
+    "mememind_io",
+    "peasdog",
+    "SeanJohnCollins",
+    "LordsServant",
+    "AwakeJourno",
+    "Salen",
+    "PhiAgent",
+    "RowanFamiliar",
+];
+
+/// A manual verdict on an author
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Mark {
+    Allow,
+    Deny,
+}
+
+/// Everything recorded about one author
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AuthorRecord {
+    /// Name as last seen
+    pub name: String,
+    #[serde(default)]
+    pub posts: u32,
+    /// Posts the filter called spam
+    #[serde(default)]
+    pub spam: u32,
+    /// Sum of the posts' scores
+    #[serde(default)]
+    pub score_total: u64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub mark: Option<Mark>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub note: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_seen: Option<DateTime<Utc>>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    recent: Vec<String>,
+}
+
+impl AuthorRecord {
+    fn new(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            posts: 0,
+            spam: 0,
+            score_total: 0,
+            mark: None,
+            note: None,
+            last_seen: None,
+            recent: Vec::new(),
+        }
+    }
+
+    pub fn average_score(&self) -> Option<f64> {
+        (self.posts > 0).then(|| self.score_total as f64 / self.posts as f64)
+    }
+
+    pub fn spam_ratio(&self) -> Option<f64> {
+        (self.posts > 0).then(|| self.spam as f64 / self.posts as f64)
+    }
+
+    /// Score change for this author's posts. A mark decides it outright;
+    /// otherwise the history counts more with more posts and less as it ages.
+    pub fn reputation(&self, now: DateTime<Utc>) -> i32 {
+        match self.mark {
+            Some(Mark::Allow) => return ALLOW_WEIGHT,
+            Some(Mark::Deny) => return DENY_WEIGHT,
+            None => {}
+        }
+        let (Some(average), Some(spam_ratio), Some(last_seen)) =
+            (self.average_score(), self.spam_ratio(), self.last_seen)
+        else {
+            return 0;
+        };
+        // Both in -1..=1: how far above the middle the posts score, and how
+        // rarely they are spam
+        let quality = ((average - 50.0) / 50.0 + (1.0 - 2.0 * spam_ratio)) / 2.0;
+        let confidence = self.posts as f64 / (self.posts as f64 + PRIOR_POSTS);
+        let age_days = (now - last_seen).num_hours().max(0) as f64 / 24.0;
+        let decay = 0.5f64.powf(age_days / HALF_LIFE_DAYS);
+        (MAX_LEARNED_WEIGHT as f64 * quality * confidence * decay).round() as i32
+    }
+}
+
+/// Authors keyed by lowercased name
+#[derive(Debug, Clone, Default)]
+pub struct AuthorStore {
+    authors: HashMap<String, AuthorRecord>,
+}
+
+impl AuthorStore {
+    pub fn parse(src: &str) -> Result<Self, CorpusError> {
+        let records: Vec<AuthorRecord> = parse_lines(src)?;
+        let authors = records.into_iter().map(|r| (r.name.to_lowercase(), r)).collect();
+        Ok(Self { authors })
+    }
+
+    /// A store with only the authors that used to be trusted outright, marked allow
+    pub fn seeded() -> Self {
+        let mut store = Self::default();
+        for name in FORMER_QUALITY_AUTHORS {
+            store.mark(name, Some(Mark::Allow), Some("formerly a built-in quality author".to_string()));
+        }
+        store
+    }
+
+    /// Read a store file; a missing file is a new, [seeded](Self::seeded) store
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, CorpusError> {
+        let path = path.as_ref();
+        match std::fs::read_to_string(path) {
+            Ok(src) => Self::parse(&src),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Self::seeded()),
+            Err(e) => Err(CorpusError {
+                line: None,
+                message: format!("failed to read {}: {}", path.display(), e),
+            }),
+        }
+    }
+
+    /// Write every author, replacing the file
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), CorpusError> {
+        let mut records: Vec<&AuthorRecord> = self.authors.values().collect();
+        records.sort_by_key(|r| r.name.to_lowercase());
+        write_lines(path.as_ref(), &records)
+    }
+
+    pub fn get(&self, name: &str) -> Option<&AuthorRecord> {
+        self.authors.get(&name.to_lowercase())
+    }
+
+    /// All authors, by name
+    pub fn records(&self) -> Vec<&AuthorRecord> {
+        let mut records: Vec<&AuthorRecord> = self.authors.values().collect();
+        records.sort_by_key(|r| r.name.to_lowercase());
+        records
+    }
+
+    /// Score change and its label per lowercased name, for authors whose
+    /// reputation changes their score
+    pub fn reputations(&self, now: DateTime<Utc>) -> HashMap<String, (i32, String)> {
+        self.authors
+            .iter()
+            .filter_map(|(key, record)| {
+                let delta = record.reputation(now);
+                let label = match record.mark {
+                    Some(Mark::Allow) => format!("Trusted author: {}", record.name),
+                    Some(Mark::Deny) => format!("Blocked author: {}", record.name),
+                    None if delta > 0 => format!("Good track record: {} ({} posts)", record.name, record.posts),
+                    None => format!("Spam history: {} ({}/{} posts spam)", record.name, record.spam, record.posts),
+                };
+                (delta != 0).then(|| (key.clone(), (delta, label)))
+            })
+            .collect()
+    }
+
+    fn entry(&mut self, name: &str) -> &mut AuthorRecord {
+        let record = self
+            .authors
+            .entry(name.to_lowercase())
+            .or_insert_with(|| AuthorRecord::new(name));
+        record.name = name.to_string();
+        record
+    }
+
+    /// Count a scored post. Returns false if the post was already counted.
+    pub fn record(&mut self, author: &str, post_id: &str, score: u32, is_spam: bool) -> bool {
+        let record = self.entry(author);
+        if record.recent.iter().any(|id| id == post_id) {
+            return false;
+        }
+        record.posts += 1;
+        record.spam += is_spam as u32;
+        record.score_total += score as u64;
+        record.last_seen = Some(Utc::now());
+        record.recent.push(post_id.to_string());
+        if record.recent.len() > RECENT_IDS {
+            record.recent.remove(0);
+        }
+        true
+    }
+
+    /// Set or clear the manual mark on an author
+    pub fn mark(&mut self, author: &str, mark: Option<Mark>, note: Option<String>) {
+        let record = self.entry(author);
+        record.mark = mark;
+        record.note = note;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+
+    #[test]
+    fn test_history_reputation() {
+        let mut store = AuthorStore::default();
+        for i in 0..10 {
+            store.record("Shill", &format!("p{}", i), 10, true);
+        }
+        assert!(!store.record("shill", "p3", 10, true));
+        let shill = store.get("SHILL").unwrap();
+        assert_eq!(shill.posts, 10);
+        assert_eq!(shill.spam_ratio(), Some(1.0));
+
+        let now = Utc::now();
+        assert_eq!(shill.reputation(now), -12);
+        // Older history counts for less
+        assert_eq!(shill.reputation(now + Duration::days(30)), -6);
+    }
+
+    #[test]
+    fn test_marks_decide() {
+        let mut store = AuthorStore::default();
+        store.record("mind", "p1", 5, true);
+        store.mark("Mind", Some(Mark::Allow), Some("writes good papers".into()));
+        assert_eq!(store.get("mind").unwrap().reputation(Utc::now()), ALLOW_WEIGHT);
+
+        let saved = store.records().iter().map(|r| serde_json::to_string(r).unwrap()).collect::<Vec<_>>().join("\n");
+        let loaded = AuthorStore::parse(&saved).unwrap();
+        assert_eq!(loaded.get("mind").unwrap().mark, Some(Mark::Allow));
+        assert_eq!(loaded.get("mind").unwrap().posts, 1);
+        store.mark("mind", None, None);
+        assert!(store.get("mind").unwrap().reputation(Utc::now()) < 0);
+    }
+
+    #[test]
+    fn test_new_store_trusts_former_quality_authors() {
+        let path = std::env::temp_dir().join(format!("moltbook_authors_{}.jsonl", std::process::id()));
+        let store = AuthorStore::load(&path).unwrap();
+        assert_eq!(store.get("MEMEMIND_IO").unwrap().mark, Some(Mark::Allow));
+
+        let reputations = store.reputations(Utc::now());
+        assert_eq!(reputations.len(), FORMER_QUALITY_AUTHORS.len());
+        assert_eq!(reputations["phiagent"], (ALLOW_WEIGHT, "Trusted author: PhiAgent".to_string()));
+    }
+}
@@ -0,0 +1,141 @@
+//! Threshold sweeps over a labeled corpus
+
+use serde::Serialize;
+
+use super::eval::Confusion;
+use super::Sample;
+use crate::filter::SpamFilter;
+
+/// Filter performance at one threshold
+#[derive(Debug, Clone, Serialize)]
+pub struct ThresholdPoint {
+    pub threshold: u32,
+    pub confusion: Confusion,
+    pub precision: f64,
+    pub recall: f64,
+    pub false_positive_rate: f64,
+    pub f1: f64,
+}
+
+/// ROC/PR curve of the filter over every possible threshold
+#[derive(Debug, Clone, Serialize)]
+pub struct Calibration {
+    pub samples: usize,
+    /// One point for each threshold from 0 (nothing is spam) to 101 (everything is)
+    pub points: Vec<ThresholdPoint>,
+    /// Area under the ROC curve
+    pub roc_auc: f64,
+    pub target_fpr: f64,
+    /// Lowest threshold that catches the most spam while its false-positive
+    /// rate stays within the target; `None` if no such threshold catches any
+    pub suggested: Option<u32>,
+}
+
+impl Calibration {
+    /// Score every sample once and sweep the threshold
+    pub fn run(filter: &SpamFilter, samples: &[Sample], target_fpr: f64) -> Self {
+        let scored: Vec<_> = samples
+            .iter()
+            .map(|s| (s.label, filter.analyze(&s.title, &s.content, s.author.as_deref()).score))
+            .collect();
+
+        let points: Vec<ThresholdPoint> = (0..=101)
+            .map(|threshold| {
+                let mut confusion = Confusion::default();
+                for &(label, score) in &scored {
+                    confusion.add(label, score < threshold);
+                }
+                ThresholdPoint {
+                    threshold,
+                    confusion,
+                    precision: confusion.precision(),
+                    recall: confusion.recall(),
+                    false_positive_rate: confusion.false_positive_rate(),
+                    f1: confusion.f1(),
+                }
+            })
+            .collect();
+
+        // Raising the threshold only moves posts towards spam, so both rates grow with it
+        let roc_auc = points
+            .windows(2)
+            .map(|w| {
+                let (a, b) = (&w[0], &w[1]);
+                (b.false_positive_rate - a.false_positive_rate) * (a.recall + b.recall) / 2.0
+            })
+            .sum();
+        // Within the target, the lowest threshold that catches the most spam:
+        // higher ones only flag more posts without catching more
+        let within: Vec<&ThresholdPoint> = points.iter().filter(|p| p.false_positive_rate <= target_fpr).collect();
+        let best_recall = within.iter().map(|p| p.recall).fold(0.0, f64::max);
+        let suggested = within
+            .iter()
+            .find(|p| best_recall > 0.0 && p.recall == best_recall)
+            .map(|p| p.threshold);
+
+        Self { samples: samples.len(), points, roc_auc, target_fpr, suggested }
+    }
+
+    pub fn point(&self, threshold: u32) -> Option<&ThresholdPoint> {
+        self.points.iter().find(|p| p.threshold == threshold)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::corpus::Label;
+
+    fn sample(title: &str, content: &str, label: Label) -> Sample {
+        Sample {
+            id: None,
+            title: title.to_string(),
+            content: content.to_string(),
+            author: None,
+            label,
+            category: None,
+        }
+    }
+
+    #[test]
+    fn test_calibration_suggests_threshold() {
+        let samples = vec![
+            sample("🦞 Minted CLAW", "Mint CLAW tokens now, airdrop!", Label::Spam),
+            sample("Hourly check-in", "Still here, checking in.", Label::Spam),
+            sample(
+                "How do you cache embeddings?",
+                "I'm storing embeddings in sqlite and the lookups get slow past a million rows. \
+                 Has anyone tried an approximate index, and how did recall hold up in practice?",
+                Label::Ham,
+            ),
+        ];
+        let calibration = Calibration::run(&SpamFilter::new(), &samples, 0.0);
+
+        assert_eq!(calibration.points.len(), 102);
+        assert_eq!(calibration.point(0).unwrap().recall, 0.0);
+        assert_eq!(calibration.point(101).unwrap().false_positive_rate, 1.0);
+        assert!((calibration.roc_auc - 1.0).abs() < 1e-9);
+
+        let suggested = calibration.suggested.unwrap();
+        let point = calibration.point(suggested).unwrap();
+        assert_eq!(point.false_positive_rate, 0.0);
+        assert_eq!(point.recall, 1.0);
+    }
+
+    #[test]
+    fn test_no_suggestion_without_caught_spam() {
+        let spam = sample("Hourly check-in", "Still here, checking in.", Label::Spam);
+        let filter = SpamFilter::new();
+        let spam_score = filter.analyze(&spam.title, &spam.content, None).score;
+
+        // Only spam: the threshold just above its score, not 101
+        let only_spam = Calibration::run(&filter, std::slice::from_ref(&spam), 0.0);
+        assert_eq!(only_spam.suggested, Some(spam_score + 1));
+
+        // Ham scoring lower than the spam: catching it always flags the ham
+        let ham = sample("Hi", "", Label::Ham);
+        assert!(filter.analyze(&ham.title, &ham.content, None).score < spam_score);
+        let calibration = Calibration::run(&filter, &[spam, ham], 0.0);
+        assert_eq!(calibration.suggested, None);
+    }
+}
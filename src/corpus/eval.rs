@@ -0,0 +1,225 @@
+//! Precision, recall and per-rule statistics over a labeled corpus
+
+use serde::Serialize;
+
+use super::{Label, Sample};
+use crate::filter::{SpamAnalysis, SpamFilter};
+
+/// Counts of predictions against true labels; spam is the positive class
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct Confusion {
+    pub true_positives: usize,
+    pub false_positives: usize,
+    pub true_negatives: usize,
+    pub false_negatives: usize,
+}
+
+impl Confusion {
+    pub fn add(&mut self, actual: Label, predicted_spam: bool) {
+        match (actual, predicted_spam) {
+            (Label::Spam, true) => self.true_positives += 1,
+            (Label::Ham, true) => self.false_positives += 1,
+            (Label::Ham, false) => self.true_negatives += 1,
+            (Label::Spam, false) => self.false_negatives += 1,
+        }
+    }
+
+    /// Share of posts flagged as spam that are spam; 0 if nothing was flagged
+    pub fn precision(&self) -> f64 {
+        ratio(self.true_positives, self.true_positives + self.false_positives)
+    }
+
+    /// Share of spam that was flagged; 0 if there is no spam
+    pub fn recall(&self) -> f64 {
+        ratio(self.true_positives, self.true_positives + self.false_negatives)
+    }
+
+    pub fn f1(&self) -> f64 {
+        let (p, r) = (self.precision(), self.recall());
+        if p + r == 0.0 { 0.0 } else { 2.0 * p * r / (p + r) }
+    }
+
+    /// Share of ham that was flagged as spam; 0 if there is no ham
+    pub fn false_positive_rate(&self) -> f64 {
+        ratio(self.false_positives, self.false_positives + self.true_negatives)
+    }
+}
+
+fn ratio(n: usize, d: usize) -> f64 {
+    if d == 0 { 0.0 } else { n as f64 / d as f64 }
+}
+
+/// How often a rule fired on spam and on ham
+#[derive(Debug, Clone, Serialize)]
+pub struct FlagStats {
+    pub rule: String,
+    pub spam_hits: usize,
+    pub ham_hits: usize,
+    /// Share of spam samples the rule fired on
+    pub spam_rate: f64,
+    /// Share of ham samples the rule fired on
+    pub ham_rate: f64,
+}
+
+/// A sample the filter got wrong
+#[derive(Debug, Clone, Serialize)]
+pub struct Misclassified {
+    pub id: Option<String>,
+    pub title: String,
+    pub category: Option<String>,
+    pub score: u32,
+    pub flags: Vec<String>,
+    pub positive_signals: Vec<String>,
+}
+
+/// Result of running the filter over a labeled corpus
+#[derive(Debug, Clone, Serialize)]
+pub struct EvalReport {
+    pub threshold: u32,
+    pub samples: usize,
+    pub confusion: Confusion,
+    pub precision: f64,
+    pub recall: f64,
+    pub f1: f64,
+    pub flags: Vec<FlagStats>,
+    /// Ham scored as spam, lowest score first
+    pub worst_false_positives: Vec<Misclassified>,
+    /// Spam that got through, highest score first
+    pub worst_false_negatives: Vec<Misclassified>,
+}
+
+impl EvalReport {
+    /// Analyze every sample and keep the `top` worst mistakes of each kind
+    pub fn run(filter: &SpamFilter, samples: &[Sample], top: usize) -> Self {
+        let analyses: Vec<SpamAnalysis> = samples
+            .iter()
+            .map(|s| filter.analyze(&s.title, &s.content, s.author.as_deref()))
+            .collect();
+
+        let mut confusion = Confusion::default();
+        for (sample, analysis) in samples.iter().zip(&analyses) {
+            confusion.add(sample.label, analysis.is_spam);
+        }
+
+        let spam_total = samples.iter().filter(|s| s.label == Label::Spam).count();
+        let ham_total = samples.len() - spam_total;
+        let flags = filter
+            .rules()
+            .map(|rule| {
+                let fired = |label: Label| {
+                    samples
+                        .iter()
+                        .zip(&analyses)
+                        .filter(|(s, a)| s.label == label && a.hits.iter().any(|h| h.rule == rule.name()))
+                        .count()
+                };
+                let (spam_hits, ham_hits) = (fired(Label::Spam), fired(Label::Ham));
+                FlagStats {
+                    rule: rule.name().to_string(),
+                    spam_hits,
+                    ham_hits,
+                    spam_rate: ratio(spam_hits, spam_total),
+                    ham_rate: ratio(ham_hits, ham_total),
+                }
+            })
+            .collect();
+
+        let mistakes = |label: Label| {
+            let mut wrong: Vec<(&Sample, &SpamAnalysis)> = samples
+                .iter()
+                .zip(&analyses)
+                .filter(|(s, a)| s.label == label && a.is_spam != (label == Label::Spam))
+                .collect();
+            // Most confident mistakes first
+            wrong.sort_by_key(|(_, a)| a.score);
+            if label == Label::Spam {
+                wrong.reverse();
+            }
+            wrong
+                .into_iter()
+                .take(top)
+                .map(|(s, a)| Misclassified {
+                    id: s.id.clone(),
+                    title: s.title.clone(),
+                    category: s.category.clone(),
+                    score: a.score,
+                    flags: a.flags.clone(),
+                    positive_signals: a.positive_signals.clone(),
+                })
+                .collect()
+        };
+
+        Self {
+            threshold: filter.threshold(),
+            samples: samples.len(),
+            confusion,
+            precision: confusion.precision(),
+            recall: confusion.recall(),
+            f1: confusion.f1(),
+            flags,
+            worst_false_positives: mistakes(Label::Ham),
+            worst_false_negatives: mistakes(Label::Spam),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample(title: &str, content: &str, label: Label) -> Sample {
+        Sample {
+            id: None,
+            title: title.to_string(),
+            content: content.to_string(),
+            author: None,
+            label,
+            category: None,
+        }
+    }
+
+    #[test]
+    fn test_eval_report() {
+        let samples = vec![
+            sample("🦞 Minted CLAW", "Mint CLAW tokens now, airdrop!", Label::Spam),
+            sample("Hourly check-in", "Still here, checking in.", Label::Spam),
+            sample(
+                "How do you cache embeddings?",
+                "I'm storing embeddings in sqlite and the lookups get slow past a million rows. \
+                 Has anyone tried an approximate index, and how did recall hold up in practice?",
+                Label::Ham,
+            ),
+            sample("Minted a new API", "Spam-looking title but a fine post: CLAW", Label::Ham),
+        ];
+        let report = EvalReport::run(&SpamFilter::new(), &samples, 5);
+
+        assert_eq!(report.samples, 4);
+        assert_eq!(
+            report.confusion,
+            Confusion { true_positives: 1, false_positives: 1, true_negatives: 1, false_negatives: 1 }
+        );
+        assert_eq!((report.precision, report.recall, report.f1), (0.5, 0.5, 0.5));
+        assert_eq!(report.worst_false_positives[0].title, "Minted a new API");
+        assert_eq!(report.worst_false_negatives[0].title, "Hourly check-in");
+
+        let stats = |rule: &str| {
+            let f = report.flags.iter().find(|f| f.rule == rule).unwrap();
+            (f.spam_hits, f.ham_hits, f.spam_rate, f.ham_rate)
+        };
+        assert_eq!(stats("claw"), (1, 1, 0.5, 0.5));
+        assert_eq!(stats("crypto"), (1, 0, 0.5, 0.0));
+        assert_eq!(stats("content_length"), (2, 1, 1.0, 0.5));
+        assert_eq!(stats("question"), (0, 1, 0.0, 0.5));
+        assert_eq!(stats("promo"), (0, 0, 0.0, 0.0));
+    }
+
+    #[test]
+    fn test_confusion_metrics() {
+        let c = Confusion { true_positives: 8, false_positives: 2, true_negatives: 18, false_negatives: 2 };
+        assert_eq!(c.precision(), 0.8);
+        assert_eq!(c.recall(), 0.8);
+        assert!((c.f1() - 0.8).abs() < 1e-9);
+        assert_eq!(c.false_positive_rate(), 0.1);
+        assert_eq!(Confusion::default().precision(), 0.0);
+    }
+}
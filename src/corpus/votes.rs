@@ -0,0 +1,216 @@
+//! Your own votes, kept as labels
+//!
+//! The vote commands append one line per vote to a history file:
+//!
+//! ```json
+//! {"post_id": "p1", "title": "Minted CLAW", "content": "...", "author": "x", "action": "downvote", "at": "2026-01-01T00:00:00Z"}
+//! ```
+//!
+//! The latest action on a post wins, so an `unvote` cancels the vote before it.
+//! Votes cast by id start without the post; [`VoteHistory::fill`] adds it once
+//! the post is fetched for another reason, such as `feed`.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::Path;
+
+use super::{append_lines, parse_lines, write_lines, CorpusError, Label, Sample};
+use crate::jsonl::read_lines;
+use crate::moltbook::Post;
+
+/// Category given to samples derived from votes
+const VOTE_CATEGORY: &str = "vote";
+
+/// What was done to a post
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Vote {
+    Upvote,
+    Downvote,
+    Unvote,
+}
+
+impl Vote {
+    /// The label a vote implies; an upvote marks ham and a downvote spam
+    pub fn label(self) -> Option<Label> {
+        match self {
+            Vote::Upvote => Some(Label::Ham),
+            Vote::Downvote => Some(Label::Spam),
+            Vote::Unvote => None,
+        }
+    }
+}
+
+/// One vote with the post it was cast on
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VoteRecord {
+    pub post_id: String,
+    #[serde(default)]
+    pub title: String,
+    #[serde(default)]
+    pub content: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub author: Option<String>,
+    pub action: Vote,
+    pub at: DateTime<Utc>,
+}
+
+impl VoteRecord {
+    pub fn new(post: &Post, action: Vote) -> Self {
+        Self {
+            post_id: post.id.clone(),
+            title: post.title.clone(),
+            content: post.content.clone(),
+            author: post.author.clone(),
+            action,
+            at: Utc::now(),
+        }
+    }
+
+    /// A vote on a post known only by its id
+    pub fn for_id(post_id: &str, action: Vote) -> Self {
+        Self {
+            post_id: post_id.to_string(),
+            title: String::new(),
+            content: String::new(),
+            author: None,
+            action,
+            at: Utc::now(),
+        }
+    }
+
+    /// Whether the post's text and author are still missing
+    pub fn is_bare(&self) -> bool {
+        self.title.is_empty() && self.content.is_empty() && self.author.is_none()
+    }
+
+    /// Append the vote to a history file, creating it if needed
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), CorpusError> {
+        append_lines(path.as_ref(), std::slice::from_ref(self))
+    }
+}
+
+/// Every vote recorded in a history file, oldest first
+#[derive(Debug, Clone, Default)]
+pub struct VoteHistory {
+    pub records: Vec<VoteRecord>,
+}
+
+impl VoteHistory {
+    pub fn parse(src: &str) -> Result<Self, CorpusError> {
+        Ok(Self { records: parse_lines(src)? })
+    }
+
+    /// Read a history file; a missing file is an empty history
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, CorpusError> {
+        Ok(Self { records: read_lines(path.as_ref())? })
+    }
+
+    /// Write every vote, replacing the file
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), CorpusError> {
+        write_lines(path.as_ref(), &self.records)
+    }
+
+    /// Add a post's text and author to the votes cast on it by id. Returns
+    /// whether any vote changed.
+    pub fn fill(&mut self, post: &Post) -> bool {
+        let mut changed = false;
+        for record in self.records.iter_mut().filter(|r| r.post_id == post.id && r.is_bare()) {
+            record.title = post.title.clone();
+            record.content = post.content.clone();
+            record.author = post.author.clone();
+            changed = true;
+        }
+        changed
+    }
+
+    /// The standing vote on each post, ignoring posts whose latest action is `unvote`
+    pub fn current(&self) -> Vec<&VoteRecord> {
+        let mut latest: HashMap<&str, &VoteRecord> = HashMap::new();
+        for record in &self.records {
+            let entry = latest.entry(&record.post_id).or_insert(record);
+            if record.at >= entry.at {
+                *entry = record;
+            }
+        }
+        let mut current: Vec<&VoteRecord> =
+            latest.into_values().filter(|r| r.action != Vote::Unvote).collect();
+        current.sort_by_key(|r| r.at);
+        current
+    }
+
+    /// Standing votes as labeled samples, for training; votes still missing
+    /// their post are left out
+    pub fn samples(&self) -> Vec<Sample> {
+        self.current()
+            .into_iter()
+            .filter(|r| !r.is_bare())
+            .filter_map(|r| {
+                Some(Sample {
+                    id: Some(r.post_id.clone()),
+                    title: r.title.clone(),
+                    content: r.content.clone(),
+                    author: r.author.clone(),
+                    label: r.action.label()?,
+                    category: Some(VOTE_CATEGORY.to_string()),
+                })
+            })
+            .collect()
+    }
+
+    /// Upvotes minus downvotes per author, keyed by lowercased name
+    pub fn author_balance(&self) -> HashMap<String, i32> {
+        let mut balance = HashMap::new();
+        for record in self.current() {
+            let Some(author) = &record.author else { continue };
+            let vote = match record.action {
+                Vote::Upvote => 1,
+                Vote::Downvote => -1,
+                Vote::Unvote => 0,
+            };
+            *balance.entry(author.to_lowercase()).or_insert(0) += vote;
+        }
+        balance
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_latest_vote_wins() {
+        let src = "\
+{\"post_id\": \"p1\", \"title\": \"CLAW\", \"author\": \"Shill\", \"action\": \"downvote\", \"at\": \"2026-01-01T00:00:00Z\"}
+{\"post_id\": \"p2\", \"title\": \"Ok\", \"author\": \"shill\", \"action\": \"downvote\", \"at\": \"2026-01-01T00:01:00Z\"}
+{\"post_id\": \"p2\", \"title\": \"Ok\", \"author\": \"shill\", \"action\": \"unvote\", \"at\": \"2026-01-01T00:02:00Z\"}
+{\"post_id\": \"p3\", \"title\": \"Nice\", \"author\": \"mind\", \"action\": \"upvote\", \"at\": \"2026-01-01T00:03:00Z\"}
+";
+        let history = VoteHistory::parse(src).unwrap();
+        let samples = history.samples();
+        assert_eq!(samples.len(), 2);
+        assert_eq!(samples[0].label, Label::Spam);
+        assert_eq!(samples[1].label, Label::Ham);
+
+        let balance = history.author_balance();
+        assert_eq!(balance["shill"], -1);
+        assert_eq!(balance["mind"], 1);
+    }
+
+    #[test]
+    fn test_fill_bare_votes() {
+        let mut history = VoteHistory { records: vec![VoteRecord::for_id("p1", Vote::Downvote)] };
+        assert!(history.samples().is_empty());
+        assert!(history.author_balance().is_empty());
+
+        let post: Post = serde_json::from_value(serde_json::json!({
+            "id": "p1", "title": "Minted CLAW", "content": "Buy now", "author": "Shill"
+        }))
+        .unwrap();
+        assert!(history.fill(&post));
+        assert!(!history.fill(&post));
+        assert_eq!(history.samples()[0].title, "Minted CLAW");
+        assert_eq!(history.author_balance()["shill"], -1);
+    }
+}
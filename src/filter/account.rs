@@ -0,0 +1,124 @@
+//! Signals from the account behind a post rather than the post itself
+
+use chrono::{DateTime, Duration, Utc};
+
+use super::rule::RuleHit;
+use crate::moltbook::Profile;
+
+/// Accounts younger than this are brand new
+const NEW_ACCOUNT_HOURS: i64 = 24;
+const NEW_ACCOUNT_WEIGHT: i32 = -15;
+
+/// Accounts younger than this are still young
+const YOUNG_ACCOUNT_DAYS: i64 = 7;
+const YOUNG_ACCOUNT_WEIGHT: i32 = -5;
+
+/// Posts after which an account should have earned some karma
+const KARMALESS_POSTS: i32 = 20;
+const KARMALESS_WEIGHT: i32 = -20;
+
+/// Accounts following at least this many others, and this many times more
+/// than follow them back, are farming follows
+const FARMING_FOLLOWING: i32 = 100;
+const FARMING_RATIO: i32 = 10;
+const FARMING_WEIGHT: i32 = -15;
+
+/// Karma and age of an account that has earned some trust
+const ESTABLISHED_KARMA: i32 = 100;
+const ESTABLISHED_DAYS: i64 = 30;
+const ESTABLISHED_WEIGHT: i32 = 5;
+
+/// Hits for what an author's profile says about their account. Each signal is
+/// its own hit, labeled with `Account:` so it reads apart from content flags.
+pub fn account_hits(profile: &Profile, now: DateTime<Utc>) -> Vec<RuleHit> {
+    let mut hits = Vec::new();
+    let age = profile.created().map(|created| now - created);
+
+    match age {
+        Some(age) if age < Duration::hours(NEW_ACCOUNT_HOURS) => hits.push(RuleHit::new(
+            "account_age",
+            NEW_ACCOUNT_WEIGHT,
+            format!("Account: brand new ({} hours old)", age.num_hours().max(0)),
+        )),
+        Some(age) if age < Duration::days(YOUNG_ACCOUNT_DAYS) => hits.push(RuleHit::new(
+            "account_age",
+            YOUNG_ACCOUNT_WEIGHT,
+            format!("Account: {} days old", age.num_days()),
+        )),
+        _ => {}
+    }
+
+    if profile.karma <= 0 && profile.post_count >= KARMALESS_POSTS {
+        hits.push(RuleHit::new(
+            "account_karma",
+            KARMALESS_WEIGHT,
+            format!("Account: no karma after {} posts", profile.post_count),
+        ));
+    }
+
+    if profile.following >= FARMING_FOLLOWING && profile.following > profile.followers.saturating_mul(FARMING_RATIO) {
+        hits.push(RuleHit::new(
+            "account_follows",
+            FARMING_WEIGHT,
+            format!(
+                "Account: follow farming (follows {}, followed by {})",
+                profile.following, profile.followers
+            ),
+        ));
+    }
+
+    let established = age.is_some_and(|age| age >= Duration::days(ESTABLISHED_DAYS));
+    if established && profile.karma >= ESTABLISHED_KARMA && hits.is_empty() {
+        hits.push(RuleHit::new(
+            "account_established",
+            ESTABLISHED_WEIGHT,
+            format!("Account: established ({} karma)", profile.karma),
+        ));
+    }
+
+    hits
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn profile(json: &str) -> Profile {
+        serde_json::from_str(json).unwrap()
+    }
+
+    #[test]
+    fn test_spammy_account() {
+        let now = "2026-01-10T12:00:00Z".parse().unwrap();
+        let bot = profile(
+            r#"{"id": "u1", "name": "bot", "karma": 0, "post_count": 80, "followers": 3,
+                "following": 900, "created_at": "2026-01-10T07:00:00Z"}"#,
+        );
+        let labels: Vec<String> = account_hits(&bot, now).into_iter().map(|h| h.label).collect();
+        assert_eq!(
+            labels,
+            vec![
+                "Account: brand new (5 hours old)",
+                "Account: no karma after 80 posts",
+                "Account: follow farming (follows 900, followed by 3)",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_established_account() {
+        let now = "2026-06-01T00:00:00Z".parse().unwrap();
+        let regular = profile(
+            r#"{"id": "u2", "name": "regular", "karma": 450, "post_count": 60, "followers": 120,
+                "following": 80, "created_at": "2026-01-01T00:00:00Z"}"#,
+        );
+        let hits = account_hits(&regular, now);
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].delta, ESTABLISHED_WEIGHT);
+        assert!(account_hits(&profile(r#"{"id": "u3", "name": "unknown"}"#), now).is_empty());
+
+        // Too many followers to multiply without overflowing
+        let famous = profile(r#"{"id": "u4", "name": "famous", "followers": 2147483647, "following": 500}"#);
+        assert!(account_hits(&famous, now).iter().all(|h| h.rule != "account_follows"));
+    }
+}
@@ -0,0 +1,300 @@
+//! Token-based naive Bayes classifier, trained from labeled posts
+
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use super::config::{ConfigError, Scope};
+use super::rule::{MatchSpan, PostView, Rule, RuleHit};
+
+/// Format version written to model files
+const MODEL_VERSION: u32 = 1;
+
+/// Tokens named in a hit's label
+const EXPLAINED_TOKENS: usize = 3;
+
+fn default_max_weight() -> i32 {
+    30
+}
+
+/// Document counts per token for each class.
+///
+/// Each post counts a token at most once, so a spammer repeating a word
+/// doesn't outweigh the rest of the post.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BayesModel {
+    pub version: u32,
+    /// Largest score delta the model can contribute, in either direction
+    #[serde(default = "default_max_weight")]
+    pub max_weight: i32,
+    pub spam_docs: u32,
+    pub ham_docs: u32,
+    /// `[spam, ham]` number of posts containing each token
+    pub tokens: BTreeMap<String, [u32; 2]>,
+}
+
+/// How a model judges one text
+#[derive(Debug, Clone)]
+pub struct Prediction {
+    /// Probability the text is spam
+    pub spam_probability: f64,
+    /// Known tokens with their log-likelihood ratio (positive leans spam),
+    /// strongest first
+    pub evidence: Vec<(String, f64)>,
+}
+
+impl BayesModel {
+    /// Count tokens over labeled posts, given as their full text (see
+    /// [`BayesModel::learn`]) and whether they are spam
+    pub fn train<S: AsRef<str>>(posts: impl IntoIterator<Item = (S, bool)>) -> Self {
+        let mut model = Self {
+            version: MODEL_VERSION,
+            max_weight: default_max_weight(),
+            spam_docs: 0,
+            ham_docs: 0,
+            tokens: BTreeMap::new(),
+        };
+        for (text, spam) in posts {
+            model.learn(text.as_ref(), spam);
+        }
+        model
+    }
+
+    /// Update the counts with one more labeled post. `text` should be what the
+    /// rule predicts from, the post's [`PostView::full_text`].
+    pub fn learn(&mut self, text: &str, spam: bool) {
+        let class = if spam {
+            self.spam_docs += 1;
+            0
+        } else {
+            self.ham_docs += 1;
+            1
+        };
+        let mut seen: Vec<String> = tokens(text).into_iter().map(|(_, t)| t).collect();
+        seen.sort();
+        seen.dedup();
+        for token in seen {
+            self.tokens.entry(token).or_default()[class] += 1;
+        }
+    }
+
+    /// Drop tokens seen in fewer than `min_count` posts
+    pub fn prune(&mut self, min_count: u32) {
+        self.tokens.retain(|_, [spam, ham]| *spam + *ham >= min_count);
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, ConfigError> {
+        let path = path.as_ref();
+        let src = std::fs::read_to_string(path)
+            .map_err(|e| ConfigError::new(format!("failed to read {}: {}", path.display(), e)))?;
+        let model: Self = serde_json::from_str(&src)
+            .map_err(|e| ConfigError::new(format!("invalid model {}: {}", path.display(), e)))?;
+        if model.version != MODEL_VERSION {
+            return Err(ConfigError::new(format!(
+                "model {} has version {}, expected {}; retrain it with `train`",
+                path.display(),
+                model.version,
+                MODEL_VERSION
+            )));
+        }
+        // A zero or negative weight would disable the model or turn it around
+        if model.max_weight <= 0 {
+            return Err(ConfigError::new(format!(
+                "model {} has max_weight {}, expected a positive number",
+                path.display(),
+                model.max_weight
+            )));
+        }
+        Ok(model)
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> std::io::Result<()> {
+        let json = serde_json::to_string(self).expect("models are serializable");
+        std::fs::write(path, json)
+    }
+
+    /// Log-likelihood ratio of a token, with add-one smoothing; `None` for unseen tokens
+    pub fn token_weight(&self, token: &str) -> Option<f64> {
+        let [spam, ham] = self.tokens.get(token)?;
+        let p_spam = (*spam as f64 + 1.0) / (self.spam_docs as f64 + 2.0);
+        let p_ham = (*ham as f64 + 1.0) / (self.ham_docs as f64 + 2.0);
+        Some((p_spam / p_ham).ln())
+    }
+
+    pub fn predict(&self, text: &str) -> Prediction {
+        let prior = ((self.spam_docs as f64 + 1.0) / (self.ham_docs as f64 + 1.0)).ln();
+        let mut seen: Vec<String> = tokens(text).into_iter().map(|(_, t)| t).collect();
+        seen.sort();
+        seen.dedup();
+
+        let mut evidence: Vec<(String, f64)> = seen
+            .into_iter()
+            .filter_map(|t| self.token_weight(&t).map(|w| (t, w)))
+            .collect();
+        evidence.sort_by(|a, b| b.1.abs().total_cmp(&a.1.abs()));
+
+        let log_odds = prior + evidence.iter().map(|(_, w)| w).sum::<f64>();
+        Prediction { spam_probability: 1.0 / (1.0 + (-log_odds).exp()), evidence }
+    }
+
+    /// Tokens that lean most strongly towards spam, or towards ham
+    pub fn top_tokens(&self, spam: bool, n: usize) -> Vec<(&str, f64)> {
+        let mut weighted: Vec<(&str, f64)> = self
+            .tokens
+            .keys()
+            .filter_map(|t| self.token_weight(t).map(|w| (t.as_str(), w)))
+            .filter(|(_, w)| (*w > 0.0) == spam)
+            .collect();
+        weighted.sort_by(|a, b| b.1.abs().total_cmp(&a.1.abs()));
+        weighted.truncate(n);
+        weighted
+    }
+}
+
+/// Lowercased words of at least two characters, with their byte ranges
+fn tokens(text: &str) -> Vec<((usize, usize), String)> {
+    let mut out = Vec::new();
+    let mut start = None;
+    for (i, c) in text.char_indices().chain(std::iter::once((text.len(), ' '))) {
+        match (start, c.is_alphanumeric() || c == '$') {
+            (None, true) => start = Some(i),
+            (Some(s), false) => {
+                let word = &text[s..i];
+                if word.chars().count() >= 2 {
+                    out.push(((s, i), word.to_lowercase()));
+                }
+                start = None;
+            }
+            _ => {}
+        }
+    }
+    out
+}
+
+/// Scores posts with a trained [`BayesModel`]; spam-leaning posts lose up to
+/// `max_weight` points and ham-leaning ones gain up to as many
+pub struct BayesRule {
+    model: BayesModel,
+}
+
+impl BayesRule {
+    pub fn new(model: BayesModel) -> Self {
+        Self { model }
+    }
+}
+
+impl Rule for BayesRule {
+    fn name(&self) -> &str {
+        "bayes"
+    }
+
+    fn description(&self) -> &str {
+        "Naive Bayes model trained on a labeled corpus"
+    }
+
+    fn weights(&self) -> Vec<i32> {
+        vec![-self.model.max_weight, self.model.max_weight]
+    }
+
+    fn evaluate(&self, post: &PostView) -> Option<RuleHit> {
+        let text = post.full_text();
+        let prediction = self.model.predict(text);
+        let delta = ((0.5 - prediction.spam_probability) * 2.0 * self.model.max_weight as f64).round() as i32;
+        if delta == 0 {
+            return None;
+        }
+
+        // Explain the verdict with the tokens that pushed towards it
+        let leaning: Vec<&str> = prediction
+            .evidence
+            .iter()
+            .filter(|(_, w)| (*w > 0.0) == (delta < 0))
+            .take(EXPLAINED_TOKENS)
+            .map(|(t, _)| t.as_str())
+            .collect();
+        let spans = tokens(text)
+            .into_iter()
+            .filter(|(_, t)| leaning.contains(&t.as_str()))
+            .map(|((start, end), _)| MatchSpan { scope: Scope::FullText, start, end })
+            .collect();
+
+        let mut label = format!("Naive Bayes: {:.0}% spam", prediction.spam_probability * 100.0);
+        if !leaning.is_empty() {
+            label.push_str(&format!(" ({})", leaning.join(", ")));
+        }
+        Some(RuleHit::new(self.name(), delta, label).with_spans(spans))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn model() -> BayesModel {
+        BayesModel::train([
+            ("airdrop now, claim your airdrop", true),
+            ("free airdrop for every wallet", true),
+            ("sqlite index for embeddings", false),
+            ("tuning an embeddings cache", false),
+        ])
+    }
+
+    #[test]
+    fn test_tokens_have_spans() {
+        let text = "Buy $CLAW, now!";
+        let found = tokens(text);
+        assert_eq!(found[1].1, "$claw");
+        let (start, end) = found[1].0;
+        assert_eq!(&text[start..end], "$CLAW");
+    }
+
+    #[test]
+    fn test_train_counts_documents() {
+        let model = model();
+        assert_eq!((model.spam_docs, model.ham_docs), (2, 2));
+        assert_eq!(model.tokens["airdrop"], [2, 0]);
+        assert_eq!(model.tokens["embeddings"], [0, 2]);
+    }
+
+    #[test]
+    fn test_rule_scores_and_explains() {
+        let rule = BayesRule::new(model());
+        let post = PostView::new("Airdrop", "claim it", None);
+        let spam = rule.evaluate(&post).unwrap();
+        assert!(spam.delta < 0);
+        assert!(spam.label.ends_with("(airdrop, claim)"));
+        let span = spam.spans[0];
+        assert_eq!(&post.full_text()[span.start..span.end], "Airdrop");
+
+        let ham = rule.evaluate(&PostView::new("Embeddings", "an index question", None)).unwrap();
+        assert!(ham.delta > 0);
+    }
+
+    #[test]
+    fn test_learn_updates_counts() {
+        let mut model = model();
+        model.learn("airdrop embeddings", false);
+        assert_eq!(model.ham_docs, 3);
+        assert_eq!(model.tokens["airdrop"], [2, 1]);
+        assert_eq!(model.tokens["embeddings"], [0, 3]);
+    }
+
+    #[test]
+    fn test_model_roundtrip() {
+        let model = model();
+        let json = serde_json::to_string(&model).unwrap();
+        let loaded: BayesModel = serde_json::from_str(&json).unwrap();
+        assert_eq!(loaded.tokens, model.tokens);
+    }
+
+    #[test]
+    fn test_load_rejects_bad_max_weight() {
+        let path = std::env::temp_dir().join(format!("moltbook_filter_model_{}.json", std::process::id()));
+        let mut model = model();
+        model.max_weight = 0;
+        model.save(&path).unwrap();
+        let err = BayesModel::load(&path).unwrap_err();
+        std::fs::remove_file(&path).unwrap();
+        assert!(err.message.contains("max_weight 0"), "{}", err);
+    }
+}
@@ -0,0 +1,493 @@
+//! The rule kinds that can be declared in a rule file
+
+use regex::Regex;
+use std::borrow::Cow;
+use std::collections::HashSet;
+
+use super::config::{Boundary, ConfigError, ContentKind, RuleConfig, RuleKind, Scope, Tier};
+use super::injection::InjectionRisk;
+use super::links::Link;
+use super::markdown::Region;
+use super::rule::{MatchSpan, PostView, Rule, RuleHit};
+
+/// Build the rule described by a `[[rules]]` entry
+pub fn compile(config: RuleConfig) -> Result<Box<dyn Rule>, ConfigError> {
+    Ok(match config.kind {
+        RuleKind::Pattern => {
+            let pattern = config.pattern.as_deref().unwrap_or_default();
+            let regex = bounded(pattern, config.boundary)
+                .map_err(|e| ConfigError::in_rule(&config, format!("invalid regex: {}", e)))?;
+            let near = match &config.near {
+                Some(near) => Some(Proximity {
+                    regex: bounded(&near.pattern, config.boundary)
+                        .map_err(|e| ConfigError::in_rule(&config, format!("invalid `near` regex: {}", e)))?,
+                    within: near.within,
+                    after: near.after,
+                }),
+                None => None,
+            };
+            Box::new(PatternRule { base: Tiered::new(config), regex, near })
+        }
+        RuleKind::Keywords => {
+            let terms = config
+                .list
+                .iter()
+                .map(|t| bounded(&format!("(?i){}", regex::escape(t)), config.boundary))
+                .collect::<Result<_, _>>()
+                .map_err(|e| ConfigError::in_rule(&config, format!("invalid keyword list: {}", e)))?;
+            Box::new(KeywordRule { base: Tiered::new(config), terms })
+        }
+        RuleKind::Authors => {
+            let authors = config.list.iter().map(|a| a.to_lowercase()).collect();
+            Box::new(AuthorRule { base: Tiered::new(config), authors })
+        }
+        RuleKind::Length => Box::new(LengthRule { base: Tiered::new(config) }),
+        RuleKind::Emoji => Box::new(EmojiRule { base: Tiered::new(config) }),
+        RuleKind::Caps => Box::new(CapsRule { base: Tiered::new(config) }),
+        RuleKind::Uniqueness => Box::new(UniquenessRule { base: Tiered::new(config) }),
+        RuleKind::ReplyContext => Box::new(ReplyContextRule { base: Tiered::new(config) }),
+        RuleKind::Obfuscation => Box::new(ObfuscationRule { base: Tiered::new(config) }),
+        RuleKind::LinkDomains => {
+            let domains = config.list.iter().map(|d| d.to_lowercase()).collect();
+            Box::new(LinkDomainsRule { base: Tiered::new(config), domains })
+        }
+        RuleKind::DistinctDomains => Box::new(DistinctDomainsRule { base: Tiered::new(config) }),
+        RuleKind::LinkDensity => Box::new(LinkDensityRule { base: Tiered::new(config) }),
+        RuleKind::DisguisedLinks => Box::new(DisguisedLinksRule { base: Tiered::new(config) }),
+        RuleKind::InjectionRisk => Box::new(InjectionRiskRule { base: Tiered::new(config) }),
+    })
+}
+
+/// Compile a pattern so that, with word boundaries, a match can't begin or end
+/// in the middle of a word (with start boundaries, can't begin there). Edges
+/// that aren't word characters (`$CLAW`, `CA:`) need no boundary.
+fn bounded(pattern: &str, boundary: Boundary) -> Result<Regex, regex::Error> {
+    match boundary {
+        Boundary::Word => Regex::new(&format!(
+            r"(?:\b{{start-half}}|\b{{end}})(?:{})(?:\b{{end-half}}|\b{{start}})",
+            pattern
+        )),
+        Boundary::Start => Regex::new(&format!(r"(?:\b{{start-half}}|\b{{end}})(?:{})", pattern)),
+        Boundary::Substring => Regex::new(pattern),
+    }
+}
+
+/// Scope, guards and tiers shared by all config-driven rules
+struct Tiered {
+    name: String,
+    description: String,
+    scope: Vec<Scope>,
+    applies_to: Vec<ContentKind>,
+    regions: Vec<Region>,
+    min_len: usize,
+    min_words: usize,
+    tiers: Vec<Tier>,
+}
+
+impl Tiered {
+    fn new(config: RuleConfig) -> Self {
+        Self {
+            description: config.description.unwrap_or_else(|| config.name.clone()),
+            name: config.name,
+            scope: config.scope,
+            applies_to: config.applies_to,
+            regions: config.regions,
+            min_len: config.min_len,
+            min_words: config.min_words,
+            tiers: config.tiers,
+        }
+    }
+
+    /// The scoped texts with regions the rule skips blanked out, or `None` if
+    /// the rule doesn't apply to this kind of content or they are too short to judge
+    fn texts<'a>(&self, post: &'a PostView) -> Option<Vec<(Scope, Cow<'a, str>)>> {
+        if !self.applies_to.contains(&post.kind()) {
+            return None;
+        }
+        // Quotes are only someone else's words if the author wrote something
+        // around them; a post that is all quote is the author's own
+        let mut regions = Cow::Borrowed(&self.regions[..]);
+        if regions.contains(&Region::Prose) && !regions.contains(&Region::Quote) && !post.has_own_prose() {
+            regions.to_mut().push(Region::Quote);
+        }
+        let texts: Vec<_> = self.scope.iter().map(|&s| (s, post.scope_in(s, &regions))).collect();
+        let len: usize = texts.iter().map(|(_, t)| t.len()).sum();
+        let words: usize = texts.iter().map(|(_, t)| t.split_whitespace().count()).sum();
+        (len >= self.min_len && words >= self.min_words).then_some(texts)
+    }
+
+    /// The single scoped text of rules that don't accept several scopes
+    fn text<'a>(&self, post: &'a PostView) -> Option<Cow<'a, str>> {
+        self.texts(post).and_then(|texts| texts.into_iter().next()).map(|(_, text)| text)
+    }
+
+    fn hit(&self, value: f64, author: Option<&str>) -> Option<RuleHit> {
+        let tier = self.tiers.iter().find(|t| t.contains(value))?;
+        let label = tier
+            .label
+            .replace("{count}", &value.to_string())
+            .replace("{author}", author.unwrap_or(""));
+        Some(RuleHit::new(&self.name, tier.weight, label))
+    }
+
+    /// Hit for rules that measure links, with `{domain}` expanded to the first
+    /// of them and their spans attached
+    fn link_hit(&self, value: f64, post: &PostView, links: &[&Link]) -> Option<RuleHit> {
+        let mut hit = self.hit(value, post.author())?;
+        hit.label = hit.label.replace("{domain}", links.first().map_or("", |l| l.domain()));
+        Some(hit.with_spans(links.iter().map(|l| l.span).collect()))
+    }
+}
+
+macro_rules! tiered_rule {
+    () => {
+        fn name(&self) -> &str {
+            &self.base.name
+        }
+
+        fn description(&self) -> &str {
+            &self.base.description
+        }
+
+        fn weights(&self) -> Vec<i32> {
+            self.base.tiers.iter().map(|t| t.weight).collect()
+        }
+    };
+}
+
+/// Another pattern required within a few words of each match
+struct Proximity {
+    regex: Regex,
+    within: usize,
+    after: bool,
+}
+
+impl Proximity {
+    /// The closest match of the other pattern that is near enough to `m`, in
+    /// the same sentence
+    fn find(&self, text: &str, m: regex::Match) -> Option<(usize, usize)> {
+        self.regex
+            .find_iter(text)
+            .filter(|n| n.range() != m.range())
+            .filter(|n| !self.after || n.start() >= m.end())
+            .filter_map(|n| {
+                let gap = if n.start() >= m.end() {
+                    &text[m.end()..n.start()]
+                } else if m.start() >= n.end() {
+                    &text[n.end()..m.start()]
+                } else {
+                    ""
+                };
+                if gap.contains(['.', '!', '?', ';', '\n']) {
+                    return None;
+                }
+                Some((gap.split_whitespace().count(), (n.start(), n.end())))
+            })
+            .filter(|(words, _)| *words <= self.within)
+            .min_by_key(|(words, _)| *words)
+            .map(|(_, range)| range)
+    }
+}
+
+/// Counts regex matches, optionally only those near a second pattern
+struct PatternRule {
+    base: Tiered,
+    regex: Regex,
+    near: Option<Proximity>,
+}
+
+impl Rule for PatternRule {
+    tiered_rule!();
+
+    fn evaluate(&self, post: &PostView) -> Option<RuleHit> {
+        let mut count = 0;
+        let mut spans = Vec::new();
+        for (scope, text) in self.base.texts(post)? {
+            for m in self.regex.find_iter(&text) {
+                let nearby = match &self.near {
+                    Some(near) => match near.find(&text, m) {
+                        Some(range) => Some(range),
+                        None => continue,
+                    },
+                    None => None,
+                };
+                count += 1;
+                spans.push(MatchSpan { scope, start: m.start(), end: m.end() });
+                if let Some((start, end)) = nearby {
+                    spans.push(MatchSpan { scope, start, end });
+                }
+            }
+        }
+        self.base
+            .hit(count as f64, post.author())
+            .map(|hit| hit.with_spans(spans))
+    }
+}
+
+/// Counts distinct terms from a list, case-insensitively. Each term is matched
+/// on its own, so terms that overlap ("debug" and "bug") both count.
+struct KeywordRule {
+    base: Tiered,
+    terms: Vec<Regex>,
+}
+
+impl Rule for KeywordRule {
+    tiered_rule!();
+
+    fn evaluate(&self, post: &PostView) -> Option<RuleHit> {
+        let scope = self.base.scope[0];
+        let text = self.base.text(post)?;
+        let spans: Vec<MatchSpan> = self
+            .terms
+            .iter()
+            .filter_map(|term| term.find(&text))
+            .map(|m| MatchSpan { scope, start: m.start(), end: m.end() })
+            .collect();
+        self.base
+            .hit(spans.len() as f64, post.author())
+            .map(|hit| hit.with_spans(spans))
+    }
+}
+
+/// Matches the post author against a list of names
+struct AuthorRule {
+    base: Tiered,
+    authors: HashSet<String>,
+}
+
+impl Rule for AuthorRule {
+    tiered_rule!();
+
+    fn evaluate(&self, post: &PostView) -> Option<RuleHit> {
+        let known = post
+            .author()
+            .is_some_and(|a| self.authors.contains(&a.to_lowercase()));
+        self.base.hit(if known { 1.0 } else { 0.0 }, post.author())
+    }
+}
+
+/// Measures text length in bytes
+struct LengthRule {
+    base: Tiered,
+}
+
+impl Rule for LengthRule {
+    tiered_rule!();
+
+    fn evaluate(&self, post: &PostView) -> Option<RuleHit> {
+        let text = self.base.text(post)?;
+        self.base.hit(text.len() as f64, post.author())
+    }
+}
+
+/// Counts emoji characters
+struct EmojiRule {
+    base: Tiered,
+}
+
+impl Rule for EmojiRule {
+    tiered_rule!();
+
+    fn evaluate(&self, post: &PostView) -> Option<RuleHit> {
+        let text = self.base.text(post)?;
+        let count = text.chars().filter(|c| is_emoji(*c)).count();
+        self.base.hit(count as f64, post.author())
+    }
+}
+
+/// Ratio of uppercase to alphabetic characters
+struct CapsRule {
+    base: Tiered,
+}
+
+impl Rule for CapsRule {
+    tiered_rule!();
+
+    fn evaluate(&self, post: &PostView) -> Option<RuleHit> {
+        let text = self.base.text(post)?;
+        let ratio = text.chars().filter(|c| c.is_uppercase()).count() as f64
+            / text.chars().filter(|c| c.is_alphabetic()).count().max(1) as f64;
+        self.base.hit(ratio, post.author())
+    }
+}
+
+/// Ratio of unique words to total words
+struct UniquenessRule {
+    base: Tiered,
+}
+
+impl Rule for UniquenessRule {
+    tiered_rule!();
+
+    fn evaluate(&self, post: &PostView) -> Option<RuleHit> {
+        let lower = self.base.text(post)?.to_lowercase();
+        let words: Vec<&str> = lower.split_whitespace().collect();
+        let unique: HashSet<_> = words.iter().collect();
+        let ratio = unique.len() as f64 / words.len().max(1) as f64;
+        self.base.hit(ratio, post.author())
+    }
+}
+
+/// Share of a comment's words that also appear in the post it replies to
+struct ReplyContextRule {
+    base: Tiered,
+}
+
+impl Rule for ReplyContextRule {
+    tiered_rule!();
+
+    fn evaluate(&self, post: &PostView) -> Option<RuleHit> {
+        let parent = post.parent()?;
+        let reply = content_words(&self.base.text(post)?);
+        if reply.is_empty() {
+            return None;
+        }
+        let context = content_words(parent.full_text());
+        let shared = reply.intersection(&context).count();
+        self.base.hit(shared as f64 / reply.len() as f64, post.author())
+    }
+}
+
+/// Counts characters that normalization had to un-disguise. Leetspeak and
+/// spaced letters only count in words a spam rule matched once spelled out,
+/// so "m1nt" does and "k3s" doesn't.
+struct ObfuscationRule {
+    base: Tiered,
+}
+
+impl Rule for ObfuscationRule {
+    tiered_rule!();
+
+    fn evaluate(&self, _post: &PostView) -> Option<RuleHit> {
+        None
+    }
+
+    fn evaluate_after(&self, post: &PostView, hits: &[RuleHit]) -> Option<RuleHit> {
+        self.base.text(post)?;
+        let scope = self.base.scope[0];
+        let matched: Vec<MatchSpan> = hits
+            .iter()
+            .filter(|h| h.delta < 0)
+            .flat_map(|h| h.spans.iter().map(|s| post.in_full_text(*s)))
+            .collect();
+        let folded: usize = post
+            .folded(scope)
+            .iter()
+            .filter(|f| {
+                let word = post.in_full_text(MatchSpan { scope, start: f.start, end: f.end });
+                matched.iter().any(|m| m.start < word.end && word.start < m.end)
+            })
+            .map(|f| f.changed)
+            .sum();
+        self.base.hit((post.suspicious(scope) + folded) as f64, post.author())
+    }
+}
+
+/// Counts links to listed domains and their subdomains
+struct LinkDomainsRule {
+    base: Tiered,
+    domains: Vec<String>,
+}
+
+impl Rule for LinkDomainsRule {
+    tiered_rule!();
+
+    fn evaluate(&self, post: &PostView) -> Option<RuleHit> {
+        self.base.text(post)?;
+        let links: Vec<&Link> = post
+            .links()
+            .iter()
+            .filter(|l| self.domains.iter().any(|d| l.is_on(d)))
+            .collect();
+        self.base.link_hit(links.len() as f64, post, &links)
+    }
+}
+
+/// Counts the distinct domains a post links to
+struct DistinctDomainsRule {
+    base: Tiered,
+}
+
+impl Rule for DistinctDomainsRule {
+    tiered_rule!();
+
+    fn evaluate(&self, post: &PostView) -> Option<RuleHit> {
+        self.base.text(post)?;
+        let mut seen = HashSet::new();
+        let links: Vec<&Link> = post.links().iter().filter(|l| seen.insert(l.domain())).collect();
+        self.base.link_hit(links.len() as f64, post, &links)
+    }
+}
+
+/// Links per word of the text outside links
+struct LinkDensityRule {
+    base: Tiered,
+}
+
+impl Rule for LinkDensityRule {
+    tiered_rule!();
+
+    fn evaluate(&self, post: &PostView) -> Option<RuleHit> {
+        self.base.text(post)?;
+        let links: Vec<&Link> = post.links().iter().collect();
+        if links.is_empty() {
+            return None;
+        }
+        let around: Vec<Region> = Region::ALL.into_iter().filter(|r| *r != Region::Link).collect();
+        let words = post.scope_in(Scope::FullText, &around).split_whitespace().count();
+        let density = links.len() as f64 / words.max(1) as f64;
+        self.base.link_hit((density * 100.0).round() / 100.0, post, &links)
+    }
+}
+
+/// Counts links whose host is a raw IP address or a punycode name
+struct DisguisedLinksRule {
+    base: Tiered,
+}
+
+impl Rule for DisguisedLinksRule {
+    tiered_rule!();
+
+    fn evaluate(&self, post: &PostView) -> Option<RuleHit> {
+        self.base.text(post)?;
+        let links: Vec<&Link> = post.links().iter().filter(|l| l.ip || l.is_punycode()).collect();
+        self.base.link_hit(links.len() as f64, post, &links)
+    }
+}
+
+/// Rates the post's prompt injection risk. Findings point into the text as
+/// written, so the hit carries no spans; they are in [`PostView::injection`].
+struct InjectionRiskRule {
+    base: Tiered,
+}
+
+impl Rule for InjectionRiskRule {
+    tiered_rule!();
+
+    fn evaluate(&self, post: &PostView) -> Option<RuleHit> {
+        self.base.text(post)?;
+        let risk = InjectionRisk::of(post.injection());
+        self.base.hit(risk.level() as f64, post.author())
+    }
+}
+
+/// Lowercased words that carry meaning: at least four letters, not a stopword
+fn content_words(text: &str) -> HashSet<String> {
+    const STOPWORDS: &[&str] = &[
+        "that", "this", "with", "have", "from", "they", "will", "would", "there", "their",
+        "what", "about", "which", "when", "your", "just", "like", "also", "been", "were",
+        "more", "some", "than", "then", "them", "into", "only", "very", "really", "think",
+    ];
+
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|w| w.chars().count() >= 4)
+        .map(str::to_lowercase)
+        .filter(|w| !STOPWORDS.contains(&w.as_str()))
+        .collect()
+}
+
+fn is_emoji(c: char) -> bool {
+    let n = c as u32;
+    (0x1F300..=0x1F9FF).contains(&n) || // Misc symbols, emoticons
+    (0x2600..=0x26FF).contains(&n)      // Misc symbols
+}
@@ -0,0 +1,420 @@
+use serde::de::{self, Deserializer, SeqAccess, Visitor};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+use super::markdown::Region;
+
+/// The rules compiled into the binary, used when no `--rules` file is given
+pub const DEFAULT_RULES: &str = include_str!("../../rules/default.toml");
+
+/// Error raised while loading or validating a rule file
+#[derive(Debug)]
+pub struct ConfigError {
+    pub rule: Option<String>,
+    pub line: Option<usize>,
+    pub message: String,
+}
+
+impl ConfigError {
+    pub(crate) fn new(message: impl Into<String>) -> Self {
+        Self { rule: None, line: None, message: message.into() }
+    }
+
+    pub(crate) fn in_rule(rule: &RuleConfig, message: impl Into<String>) -> Self {
+        Self {
+            rule: Some(rule.name.clone()),
+            line: Some(rule.line),
+            message: message.into(),
+        }
+    }
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match (&self.rule, self.line) {
+            (Some(rule), Some(line)) => write!(f, "rule `{}` (line {}): {}", rule, line, self.message),
+            (Some(rule), None) => write!(f, "rule `{}`: {}", rule, self.message),
+            (None, Some(line)) => write!(f, "line {}: {}", line, self.message),
+            (None, None) => write!(f, "{}", self.message),
+        }
+    }
+}
+
+impl std::error::Error for ConfigError {}
+
+/// Which part of a post a rule looks at
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Scope {
+    Title,
+    Content,
+    FullText,
+}
+
+/// Whether a text is a post or a comment
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ContentKind {
+    Post,
+    Comment,
+}
+
+/// What a rule measures
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RuleKind {
+    /// Number of regex matches
+    #[default]
+    Pattern,
+    /// Length of the text in bytes
+    Length,
+    /// Number of emoji characters
+    Emoji,
+    /// Ratio of uppercase to alphabetic characters
+    Caps,
+    /// Ratio of unique words to total words
+    Uniqueness,
+    /// 1 when the author is in `list`, 0 otherwise
+    Authors,
+    /// Number of distinct `list` terms contained in the text
+    Keywords,
+    /// Share of a comment's words that also appear in its parent post
+    ReplyContext,
+    /// Number of characters disguised with lookalikes, invisible characters,
+    /// or spacing and leetspeak in words other rules flagged
+    Obfuscation,
+    /// Number of links to a domain in `list` or its subdomains
+    LinkDomains,
+    /// Number of distinct domains linked to
+    DistinctDomains,
+    /// Links per word of the text around them
+    LinkDensity,
+    /// Number of links to a raw IP address or an international (punycode) host
+    DisguisedLinks,
+    /// Prompt injection risk of the post as written: 0 for none, 1 low,
+    /// 2 medium, 3 high
+    InjectionRisk,
+}
+
+impl RuleKind {
+    fn name(self) -> &'static str {
+        match self {
+            RuleKind::Pattern => "pattern",
+            RuleKind::Length => "length",
+            RuleKind::Emoji => "emoji",
+            RuleKind::Caps => "caps",
+            RuleKind::Uniqueness => "uniqueness",
+            RuleKind::Authors => "authors",
+            RuleKind::Keywords => "keywords",
+            RuleKind::ReplyContext => "reply_context",
+            RuleKind::Obfuscation => "obfuscation",
+            RuleKind::LinkDomains => "link_domains",
+            RuleKind::DistinctDomains => "distinct_domains",
+            RuleKind::LinkDensity => "link_density",
+            RuleKind::DisguisedLinks => "disguised_links",
+            RuleKind::InjectionRisk => "injection_risk",
+        }
+    }
+}
+
+/// Where a `pattern` or `keywords` match may start and end
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Boundary {
+    /// Matches can't start or end inside a word, so `dex` doesn't match "index"
+    #[default]
+    Word,
+    /// Matches must start at the start of a word but may end inside one, so
+    /// `debug` matches "debugging" but not "rebug"
+    Start,
+    /// Matches anywhere, including inside words
+    Substring,
+}
+
+/// Another pattern that must match close to each match of a `pattern` rule
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Near {
+    pub pattern: String,
+    /// Most words allowed between the two matches, which must also be in the
+    /// same sentence
+    #[serde(default = "default_within")]
+    pub within: usize,
+    /// Only look after the `pattern` match, not before it
+    #[serde(default)]
+    pub after: bool,
+}
+
+fn default_within() -> usize {
+    3
+}
+
+/// A score band of a rule; the first tier whose bounds fit the measurement applies
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Tier {
+    #[serde(default)]
+    pub above: Option<f64>,
+    #[serde(default)]
+    pub below: Option<f64>,
+    pub weight: i32,
+    pub label: String,
+}
+
+impl Tier {
+    pub fn contains(&self, value: f64) -> bool {
+        self.above.is_none_or(|a| value > a) && self.below.is_none_or(|b| value < b)
+    }
+}
+
+/// A single rule as written in the rule file
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct RuleConfig {
+    pub name: String,
+    #[serde(default)]
+    pub kind: RuleKind,
+    #[serde(default = "default_scope", deserialize_with = "one_or_many")]
+    pub scope: Vec<Scope>,
+    #[serde(default = "default_applies_to")]
+    pub applies_to: Vec<ContentKind>,
+    #[serde(default = "default_regions")]
+    pub regions: Vec<Region>,
+    #[serde(default)]
+    pub pattern: Option<String>,
+    #[serde(default)]
+    pub list: Vec<String>,
+    #[serde(default)]
+    pub boundary: Boundary,
+    #[serde(default)]
+    pub near: Option<Near>,
+    #[serde(default)]
+    pub min_len: usize,
+    #[serde(default)]
+    pub min_words: usize,
+    #[serde(default)]
+    pub description: Option<String>,
+    pub tiers: Vec<Tier>,
+    /// Line of the `[[rules]]` entry in the source file
+    #[serde(skip)]
+    pub line: usize,
+}
+
+fn default_scope() -> Vec<Scope> {
+    vec![Scope::FullText]
+}
+
+fn default_applies_to() -> Vec<ContentKind> {
+    vec![ContentKind::Post, ContentKind::Comment]
+}
+
+fn default_regions() -> Vec<Region> {
+    Region::ALL.to_vec()
+}
+
+fn default_threshold() -> u32 {
+    30
+}
+
+fn default_base_score() -> i32 {
+    50
+}
+
+/// A parsed and validated rule file
+#[derive(Debug, Clone)]
+pub struct FilterConfig {
+    pub threshold: u32,
+    pub base_score: i32,
+    pub rules: Vec<RuleConfig>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RawConfig {
+    #[serde(default = "default_threshold")]
+    threshold: u32,
+    #[serde(default = "default_base_score")]
+    base_score: i32,
+    #[serde(default)]
+    rules: Vec<toml::Spanned<toml::Value>>,
+}
+
+impl FilterConfig {
+    /// Parse a rule file from a TOML string
+    pub fn parse(src: &str) -> Result<Self, ConfigError> {
+        let raw: RawConfig = toml::from_str(src).map_err(|e| ConfigError {
+            rule: None,
+            line: e.span().map(|s| line_of(src, s.start)),
+            message: e.message().to_string(),
+        })?;
+
+        let mut rules: Vec<RuleConfig> = Vec::with_capacity(raw.rules.len());
+        for spanned in raw.rules {
+            let line = line_of(src, spanned.span().start);
+            let value = spanned.into_inner();
+            let name = value.get("name").and_then(|v| v.as_str()).map(str::to_string);
+
+            let mut rule = RuleConfig::deserialize(value).map_err(|e| ConfigError {
+                rule: name.clone(),
+                line: Some(line),
+                message: e.message().to_string(),
+            })?;
+            rule.line = line;
+            rule.validate()?;
+
+            if rules.iter().any(|r| r.name == rule.name) {
+                return Err(ConfigError::in_rule(&rule, "duplicate rule name"));
+            }
+            rules.push(rule);
+        }
+
+        Ok(Self { threshold: raw.threshold, base_score: raw.base_score, rules })
+    }
+}
+
+impl Default for FilterConfig {
+    fn default() -> Self {
+        Self::parse(DEFAULT_RULES).expect("built-in rules are valid")
+    }
+}
+
+impl RuleConfig {
+    fn validate(&self) -> Result<(), ConfigError> {
+        let kind = self.kind.name();
+
+        if self.tiers.is_empty() {
+            return Err(ConfigError::in_rule(self, "at least one tier is required"));
+        }
+        if self.scope.is_empty() {
+            return Err(ConfigError::in_rule(self, "scope must not be empty"));
+        }
+        if self.applies_to.is_empty() {
+            return Err(ConfigError::in_rule(self, "applies_to must not be empty"));
+        }
+        if self.regions.is_empty() {
+            return Err(ConfigError::in_rule(self, "regions must not be empty"));
+        }
+        if self.kind == RuleKind::ReplyContext && self.applies_to != [ContentKind::Comment] {
+            return Err(ConfigError::in_rule(
+                self,
+                "`reply_context` rules only apply to comments; set applies_to = [\"comment\"]",
+            ));
+        }
+        if self.kind != RuleKind::Pattern && self.scope.len() > 1 {
+            return Err(ConfigError::in_rule(
+                self,
+                format!("`{}` rules take a single scope", kind),
+            ));
+        }
+
+        match self.kind {
+            RuleKind::Pattern if self.pattern.is_none() => {
+                return Err(ConfigError::in_rule(self, "`pattern` rules require a `pattern`"));
+            }
+            RuleKind::Authors | RuleKind::Keywords | RuleKind::LinkDomains if self.list.is_empty() => {
+                return Err(ConfigError::in_rule(self, format!("`{}` rules require a `list`", kind)));
+            }
+            _ => {}
+        }
+        if self.kind != RuleKind::Pattern && self.pattern.is_some() {
+            return Err(ConfigError::in_rule(
+                self,
+                format!("`pattern` is not supported by `{}` rules", kind),
+            ));
+        }
+        if !matches!(self.kind, RuleKind::Authors | RuleKind::Keywords | RuleKind::LinkDomains)
+            && !self.list.is_empty()
+        {
+            return Err(ConfigError::in_rule(
+                self,
+                format!("`list` is not supported by `{}` rules", kind),
+            ));
+        }
+        if !matches!(self.kind, RuleKind::Pattern | RuleKind::Keywords) && self.boundary != Boundary::Word {
+            return Err(ConfigError::in_rule(
+                self,
+                format!("`boundary` is not supported by `{}` rules", kind),
+            ));
+        }
+        if self.kind != RuleKind::Pattern && self.near.is_some() {
+            return Err(ConfigError::in_rule(
+                self,
+                format!("`near` is not supported by `{}` rules", kind),
+            ));
+        }
+
+        Ok(())
+    }
+}
+
+/// 1-based line number of a byte offset
+fn line_of(src: &str, offset: usize) -> usize {
+    src[..offset.min(src.len())].matches('\n').count() + 1
+}
+
+/// Accept either `scope = "title"` or `scope = ["title", "content"]`
+fn one_or_many<'de, D>(deserializer: D) -> Result<Vec<Scope>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    struct ScopeVisitor;
+
+    impl<'de> Visitor<'de> for ScopeVisitor {
+        type Value = Vec<Scope>;
+
+        fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            f.write_str("a scope or a list of scopes")
+        }
+
+        fn visit_str<E: de::Error>(self, v: &str) -> Result<Self::Value, E> {
+            Scope::deserialize(de::value::StrDeserializer::<E>::new(v)).map(|s| vec![s])
+        }
+
+        fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+            let mut scopes = Vec::new();
+            while let Some(scope) = seq.next_element()? {
+                scopes.push(scope);
+            }
+            Ok(scopes)
+        }
+    }
+
+    deserializer.deserialize_any(ScopeVisitor)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_default_rules_parse() {
+        let config = FilterConfig::default();
+        assert_eq!(config.threshold, 30);
+        assert!(config.rules.iter().any(|r| r.name == "claw"));
+    }
+
+    #[test]
+    fn test_unknown_field_names_rule_and_line() {
+        let src = "threshold = 30\n\n[[rules]]\nname = \"claw\"\npatern = 'CLAW'\ntiers = []\n";
+        let err = FilterConfig::parse(src).unwrap_err();
+        assert_eq!(err.rule.as_deref(), Some("claw"));
+        assert_eq!(err.line, Some(3));
+        assert!(err.message.contains("patern"));
+    }
+
+    #[test]
+    fn test_near_only_on_patterns() {
+        let src = "[[rules]]\nname = \"len\"\nkind = \"length\"\nnear = { pattern = 'x' }\n\
+                   tiers = [{ above = 0, weight = 1, label = \"L\" }]\n";
+        let err = FilterConfig::parse(src).unwrap_err();
+        assert!(err.message.contains("`near`"), "{}", err);
+    }
+
+    #[test]
+    fn test_scope_list() {
+        let src = "[[rules]]\nname = \"q\"\nscope = [\"title\", \"content\"]\npattern = '\\?'\n\
+                   tiers = [{ above = 0, weight = 10, label = \"Q\" }]\n";
+        let config = FilterConfig::parse(src).unwrap();
+        assert_eq!(config.rules[0].scope, vec![Scope::Title, Scope::Content]);
+    }
+}
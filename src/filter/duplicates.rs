@@ -0,0 +1,221 @@
+//! Near-duplicate detection across a batch of posts
+//!
+//! Each post's normalized text is reduced to a MinHash signature of its word
+//! shingles, which estimates how many shingles two posts share. Posts sharing
+//! most of them are copies of the same template, even when a few words were
+//! swapped.
+
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+
+use super::rule::{PostView, RuleHit};
+use crate::jsonl::{read_lines, write_lines, DataFileError};
+
+/// Words per shingle
+const SHINGLE: usize = 2;
+
+/// Hash functions in a signature
+const SIGNATURE_LEN: usize = 64;
+
+/// Posts with fewer words are too short to call copies
+const MIN_WORDS: usize = 8;
+
+/// Score change for a copy of an earlier post
+const TEMPLATE_WEIGHT: i32 = -30;
+
+/// MinHash signature of a text's word shingles, or `None` if it's too short
+/// to fingerprint
+pub fn minhash(text: &str) -> Option<Vec<u32>> {
+    let words: Vec<String> = text
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|w| !w.is_empty())
+        .map(str::to_lowercase)
+        .collect();
+    if words.len() < MIN_WORDS {
+        return None;
+    }
+    let shingles: HashSet<u64> = words.windows(SHINGLE).map(|s| fnv1a(&s.join(" "))).collect();
+    let signature = (0..SIGNATURE_LEN as u64)
+        .map(|seed| {
+            let min = shingles.iter().map(|h| mix(h ^ seed.wrapping_mul(0x9e3779b97f4a7c15))).min();
+            (min.unwrap_or_default() >> 32) as u32
+        })
+        .collect();
+    Some(signature)
+}
+
+/// Estimated share of shingles two signatures have in common
+pub fn similarity(a: &[u32], b: &[u32]) -> f64 {
+    let same = a.iter().zip(b).filter(|(x, y)| x == y).count();
+    same as f64 / a.len().max(b.len()).max(1) as f64
+}
+
+/// The splitmix64 finalizer, turning one hash into one of many independent ones
+fn mix(mut x: u64) -> u64 {
+    x = (x ^ (x >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+    x = (x ^ (x >> 27)).wrapping_mul(0x94d049bb133111eb);
+    x ^ (x >> 31)
+}
+
+/// FNV-1a, which unlike std's hasher is stable across builds, so saved
+/// fingerprints stay comparable
+fn fnv1a(text: &str) -> u64 {
+    text.bytes().fold(0xcbf29ce484222325, |hash, b| (hash ^ b as u64).wrapping_mul(0x100000001b3))
+}
+
+/// A post's fingerprint, as kept in the window file
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Fingerprint {
+    pub id: String,
+    pub title: String,
+    pub signature: Vec<u32>,
+    /// Id of the first post seen with this text
+    pub original: String,
+    pub at: DateTime<Utc>,
+}
+
+/// A group of near-identical posts
+#[derive(Debug, Clone, Serialize)]
+pub struct Cluster {
+    /// Title of the first post seen
+    pub title: String,
+    pub ids: Vec<String>,
+}
+
+impl Cluster {
+    pub fn size(&self) -> usize {
+        self.ids.len()
+    }
+}
+
+/// Clusters posts by fingerprint as they are checked, remembering earlier ones
+pub struct DuplicateDetector {
+    min_similarity: f64,
+    seen: Vec<Fingerprint>,
+    /// Ids checked since the detector was created or loaded
+    checked: HashSet<String>,
+}
+
+impl DuplicateDetector {
+    pub fn new() -> Self {
+        Self { min_similarity: 0.6, seen: Vec::new(), checked: HashSet::new() }
+    }
+
+    /// Share of shingles two posts must have in common to count as copies
+    pub fn with_min_similarity(mut self, similarity: f64) -> Self {
+        self.min_similarity = similarity;
+        self
+    }
+
+    /// Load fingerprints saved by an earlier run, dropping those older than
+    /// `max_age`. A missing file is an empty window.
+    pub fn load(path: impl AsRef<Path>, max_age: Duration) -> Result<Self, DataFileError> {
+        let cutoff = Utc::now() - max_age;
+        let mut seen: Vec<Fingerprint> = read_lines(path.as_ref())?;
+        seen.retain(|f| f.at >= cutoff);
+        Ok(Self { seen, ..Self::new() })
+    }
+
+    /// Write every remembered fingerprint, replacing the file
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), DataFileError> {
+        write_lines(path.as_ref(), &self.seen)
+    }
+
+    /// Fingerprint a post and remember it. Returns a hit if it copies a post
+    /// seen before, in this run or in the loaded window.
+    pub fn check(&mut self, id: &str, post: &PostView) -> Option<RuleHit> {
+        self.checked.insert(id.to_string());
+        let original = match self.seen.iter().find(|f| f.id == id) {
+            Some(known) => known.original.clone(),
+            None => {
+                let signature = minhash(post.full_text())?;
+                let original = self
+                    .seen
+                    .iter()
+                    .map(|f| (similarity(&f.signature, &signature), f))
+                    .filter(|(s, _)| *s >= self.min_similarity)
+                    .max_by(|a, b| a.0.total_cmp(&b.0))
+                    .map_or_else(|| id.to_string(), |(_, f)| f.original.clone());
+                self.seen.push(Fingerprint {
+                    id: id.to_string(),
+                    title: post.title().to_string(),
+                    signature,
+                    original: original.clone(),
+                    at: Utc::now(),
+                });
+                original
+            }
+        };
+        if original == id {
+            return None;
+        }
+        let copies = self.seen.iter().filter(|f| f.original == original).count();
+        let label = format!("Template spam ({} near-identical posts)", copies);
+        Some(RuleHit::new("duplicates", TEMPLATE_WEIGHT, label))
+    }
+
+    /// Clusters of two or more posts with at least one checked in this run,
+    /// largest first
+    pub fn clusters(&self) -> Vec<Cluster> {
+        let mut groups: HashMap<&str, Vec<&Fingerprint>> = HashMap::new();
+        for fingerprint in &self.seen {
+            groups.entry(&fingerprint.original).or_default().push(fingerprint);
+        }
+        let mut clusters: Vec<Cluster> = groups
+            .into_values()
+            .filter(|group| group.len() > 1 && group.iter().any(|f| self.checked.contains(&f.id)))
+            .map(|group| Cluster {
+                title: group[0].title.clone(),
+                ids: group.iter().map(|f| f.id.clone()).collect(),
+            })
+            .collect();
+        clusters.sort_by(|a, b| b.size().cmp(&a.size()).then_with(|| a.title.cmp(&b.title)));
+        clusters
+    }
+}
+
+impl Default for DuplicateDetector {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TEMPLATE: &str = "Just minted 1000 CLAW tokens on the official launchpad today. \
+                            Early holders get the airdrop, so grab yours before the price goes to the moon!";
+
+    fn similar(a: &str, b: &str) -> f64 {
+        similarity(&minhash(a).unwrap(), &minhash(b).unwrap())
+    }
+
+    #[test]
+    fn test_small_edits_stay_close() {
+        let edited = TEMPLATE.replace("1000", "5000").replace("today", "tonight");
+        let other = "I rebuilt our retrieval pipeline around smaller chunks and the answer quality \
+                     went up noticeably, especially for long technical documents.";
+        assert!(similar(TEMPLATE, &edited) >= 0.6, "{}", similar(TEMPLATE, &edited));
+        assert!(similar(TEMPLATE, other) < 0.2, "{}", similar(TEMPLATE, other));
+        assert_eq!(minhash("gm frens"), None);
+    }
+
+    #[test]
+    fn test_copies_are_clustered() {
+        let mut detector = DuplicateDetector::new();
+        let post = |text: &str| PostView::new("Mint", text, None);
+        assert!(detector.check("a", &post(TEMPLATE)).is_none());
+        let hit = detector.check("b", &post(&TEMPLATE.replace("today", "now"))).unwrap();
+        assert_eq!(hit.label, "Template spam (2 near-identical posts)");
+        assert!(detector.check("c", &post("Totally unrelated thoughts about indexing strategies for vector stores.")).is_none());
+        // Seeing the same post again doesn't make it a copy of itself
+        assert!(detector.check("a", &post(TEMPLATE)).is_none());
+
+        let clusters = detector.clusters();
+        assert_eq!(clusters.len(), 1);
+        assert_eq!(clusters[0].ids, vec!["a", "b"]);
+    }
+}
@@ -0,0 +1,227 @@
+//! Posting-rate and flood detection per author
+//!
+//! A bot posting "still here" every five minutes writes posts that each look
+//! harmless. The detector keeps when each author posted, across runs if saved,
+//! and flags authors who post in bursts, around the clock or under the same
+//! title over and over.
+
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+
+use super::rule::RuleHit;
+use crate::jsonl::{read_lines, write_lines, DataFileError};
+
+/// Posts within one hour that make a burst
+const BURST_POSTS: usize = 6;
+const BURST_WEIGHT: i32 = -30;
+
+/// Posts within one day that make a flood
+const DAILY_POSTS: usize = 24;
+const DAILY_WEIGHT: i32 = -25;
+
+/// Repeats of one title that make a flood
+const REPEATED_TITLES: usize = 3;
+const REPEATED_WEIGHT: i32 = -20;
+
+/// When an author posted what
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PostEvent {
+    pub author: String,
+    pub post_id: String,
+    pub title: String,
+    pub at: DateTime<Utc>,
+}
+
+/// How an author has been posting
+#[derive(Debug, Clone, Serialize)]
+pub struct AuthorActivity {
+    pub author: String,
+    /// Posts in the history
+    pub posts: usize,
+    /// Posts in the 24 hours up to the author's latest
+    pub last_day: usize,
+    /// Most posts in any 60 minutes
+    pub max_hourly: usize,
+    /// Posts whose title, ignoring case and numbers, the author already used
+    pub repeated_titles: usize,
+}
+
+impl AuthorActivity {
+    fn new(author: &str, events: &[&PostEvent]) -> Self {
+        let mut times: Vec<DateTime<Utc>> = events.iter().map(|e| e.at).collect();
+        times.sort();
+        let latest = times.last().copied().unwrap_or_else(Utc::now);
+
+        let last_day = times.iter().filter(|t| latest - **t < Duration::days(1)).count();
+        let mut max_hourly = 0;
+        let mut start = 0;
+        for end in 0..times.len() {
+            while times[end] - times[start] >= Duration::hours(1) {
+                start += 1;
+            }
+            max_hourly = max_hourly.max(end - start + 1);
+        }
+
+        let titles: HashSet<String> = events.iter().map(|e| title_key(&e.title)).collect();
+
+        Self {
+            author: author.to_string(),
+            posts: events.len(),
+            last_day,
+            max_hourly,
+            repeated_titles: events.len() - titles.len(),
+        }
+    }
+
+    /// Every way the author is flooding, most severe first
+    pub fn reasons(&self) -> Vec<(i32, String)> {
+        let mut reasons = Vec::new();
+        if self.max_hourly >= BURST_POSTS {
+            reasons.push((BURST_WEIGHT, format!("{} posts in an hour", self.max_hourly)));
+        }
+        if self.last_day >= DAILY_POSTS {
+            reasons.push((DAILY_WEIGHT, format!("{} posts in a day", self.last_day)));
+        }
+        if self.repeated_titles >= REPEATED_TITLES {
+            reasons.push((REPEATED_WEIGHT, format!("same title {} more times", self.repeated_titles)));
+        }
+        reasons
+    }
+
+    pub fn is_flooding(&self) -> bool {
+        !self.reasons().is_empty()
+    }
+}
+
+/// Titles that differ only in case, spacing or numbers ("Hourly check-in #12")
+/// count as the same title
+fn title_key(title: &str) -> String {
+    title
+        .split(|c: char| !c.is_alphabetic())
+        .filter(|w| !w.is_empty())
+        .map(str::to_lowercase)
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Remembers when authors posted and flags those flooding the feed
+#[derive(Debug, Default)]
+pub struct FloodDetector {
+    events: Vec<PostEvent>,
+    /// Authors observed since the detector was created or loaded
+    observed: HashSet<String>,
+}
+
+impl FloodDetector {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Load a history saved by an earlier run, dropping posts older than
+    /// `max_age`. A missing file is an empty history.
+    pub fn load(path: impl AsRef<Path>, max_age: Duration) -> Result<Self, DataFileError> {
+        let cutoff = Utc::now() - max_age;
+        let mut events: Vec<PostEvent> = read_lines(path.as_ref())?;
+        events.retain(|e| e.at >= cutoff);
+        Ok(Self { events, ..Self::new() })
+    }
+
+    /// Write the whole history, replacing the file
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), DataFileError> {
+        write_lines(path.as_ref(), &self.events)
+    }
+
+    /// Note a post; a post already in the history is ignored
+    pub fn observe(&mut self, author: &str, post_id: &str, title: &str, at: DateTime<Utc>) {
+        self.observed.insert(author.to_lowercase());
+        if self.events.iter().any(|e| e.post_id == post_id) {
+            return;
+        }
+        self.events.push(PostEvent {
+            author: author.to_string(),
+            post_id: post_id.to_string(),
+            title: title.to_string(),
+            at,
+        });
+    }
+
+    pub fn activity(&self, author: &str) -> Option<AuthorActivity> {
+        let key = author.to_lowercase();
+        let events: Vec<&PostEvent> = self.events.iter().filter(|e| e.author.to_lowercase() == key).collect();
+        (!events.is_empty()).then(|| AuthorActivity::new(author, &events))
+    }
+
+    /// A hit for posts by an author who is flooding
+    pub fn check(&self, author: &str) -> Option<RuleHit> {
+        let reasons = self.activity(author)?.reasons();
+        let delta = reasons.iter().map(|(weight, _)| *weight).min()?;
+        let details: Vec<&str> = reasons.iter().map(|(_, reason)| reason.as_str()).collect();
+        Some(RuleHit::new("flood", delta, format!("Flooding: {}", details.join(", "))))
+    }
+
+    /// Authors observed in this run who are flooding, busiest first
+    pub fn floods(&self) -> Vec<AuthorActivity> {
+        let mut names: HashMap<String, &str> = HashMap::new();
+        for event in &self.events {
+            names.insert(event.author.to_lowercase(), &event.author);
+        }
+        let mut floods: Vec<AuthorActivity> = self
+            .observed
+            .iter()
+            .filter_map(|key| self.activity(names.get(key)?))
+            .filter(AuthorActivity::is_flooding)
+            .collect();
+        floods.sort_by(|a, b| {
+            (b.max_hourly, b.last_day).cmp(&(a.max_hourly, a.last_day)).then_with(|| a.author.cmp(&b.author))
+        });
+        floods
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(minutes: i64) -> DateTime<Utc> {
+        "2026-01-01T00:00:00Z".parse::<DateTime<Utc>>().unwrap() + Duration::minutes(minutes)
+    }
+
+    #[test]
+    fn test_bursts_and_repeated_titles() {
+        let mut detector = FloodDetector::new();
+        for i in 0..8 {
+            detector.observe("checkin_bot", &format!("b{}", i), &format!("Hourly check-in #{}", i), at(i * 5));
+        }
+        detector.observe("writer", "w1", "On memory", at(0));
+        detector.observe("writer", "w2", "On planning", at(600));
+        // Seen again on a later page or run
+        detector.observe("writer", "w2", "On planning", at(600));
+
+        let bot = detector.activity("checkin_bot").unwrap();
+        assert_eq!(bot.max_hourly, 8);
+        assert_eq!(bot.repeated_titles, 7);
+        let hit = detector.check("Checkin_Bot").unwrap();
+        assert_eq!(hit.delta, BURST_WEIGHT);
+        assert_eq!(hit.label, "Flooding: 8 posts in an hour, same title 7 more times");
+
+        assert_eq!(detector.activity("writer").unwrap().posts, 2);
+        assert!(detector.check("writer").is_none());
+        let floods = detector.floods();
+        assert_eq!(floods.len(), 1);
+        assert_eq!(floods[0].author, "checkin_bot");
+    }
+
+    #[test]
+    fn test_steady_daily_flood() {
+        let mut detector = FloodDetector::new();
+        for i in 0..30 {
+            detector.observe("ticker", &format!("t{}", i), &format!("Price update {}", i), at(i * 45));
+        }
+        let activity = detector.activity("ticker").unwrap();
+        assert_eq!(activity.max_hourly, 2);
+        assert_eq!(activity.last_day, 30);
+        assert!(detector.check("ticker").unwrap().label.contains("30 posts in a day"));
+    }
+}
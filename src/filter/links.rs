@@ -0,0 +1,197 @@
+//! Links in posts and the reputation of the domains they point to
+
+use reqwest::Url;
+use serde::Serialize;
+use std::collections::HashSet;
+use std::path::Path;
+
+use super::config::ConfigError;
+use super::rule::{MatchSpan, PostView, Rule, RuleHit};
+
+/// Score change when a post links to a blocked domain
+const BLOCKED_WEIGHT: i32 = -40;
+
+/// Score change when every link in a post goes to a trusted domain
+const ALLOWED_WEIGHT: i32 = 10;
+
+/// A link found in a post
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Link {
+    /// The target as written
+    pub url: String,
+    /// Lowercased host name or IP address; international names are in punycode
+    pub host: String,
+    /// The host is an IP address rather than a name
+    pub ip: bool,
+    /// Where the link appears in the full text
+    pub span: MatchSpan,
+}
+
+impl Link {
+    /// Parse a markdown link region: `[text](url)`, `<url>` or a bare URL
+    pub(crate) fn parse(markup: &str, span: MatchSpan) -> Option<Self> {
+        let target = if markup.starts_with('[') {
+            let open = markup.find("](")?;
+            markup[open + 2..].strip_suffix(')')?
+        } else if let Some(inner) = markup.strip_prefix('<') {
+            inner.strip_suffix('>')?
+        } else {
+            markup
+        };
+        let url = Url::parse(target).ok()?;
+        let host = url.host_str()?.trim_end_matches('.').to_string();
+        Some(Self {
+            url: target.to_string(),
+            ip: url.domain().is_none(),
+            host,
+            span,
+        })
+    }
+
+    /// Host without a leading `www.`
+    pub fn domain(&self) -> &str {
+        self.host.strip_prefix("www.").unwrap_or(&self.host)
+    }
+
+    /// Whether the host is `domain` or one of its subdomains
+    pub fn is_on(&self, domain: &str) -> bool {
+        self.host == domain || self.host.strip_suffix(domain).is_some_and(|rest| rest.ends_with('.'))
+    }
+
+    /// Whether the host is an international name, which can imitate a familiar one
+    pub fn is_punycode(&self) -> bool {
+        self.host.split('.').any(|label| label.starts_with("xn--"))
+    }
+}
+
+/// A set of domains read from a file, one per line; `#` starts a comment
+#[derive(Debug, Clone, Default)]
+pub struct DomainList {
+    domains: HashSet<String>,
+}
+
+impl DomainList {
+    pub fn parse(src: &str) -> Self {
+        let domains = src
+            .lines()
+            .map(|line| line.split('#').next().unwrap_or_default().trim())
+            .filter(|line| !line.is_empty())
+            .map(|line| line.trim_start_matches("*.").trim_end_matches('.').to_lowercase())
+            .collect();
+        Self { domains }
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, ConfigError> {
+        let path = path.as_ref();
+        let src = std::fs::read_to_string(path)
+            .map_err(|e| ConfigError::new(format!("failed to read {}: {}", path.display(), e)))?;
+        Ok(Self::parse(&src))
+    }
+
+    pub fn len(&self) -> usize {
+        self.domains.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.domains.is_empty()
+    }
+
+    /// The most specific listed domain covering the link's host
+    pub fn matching(&self, link: &Link) -> Option<&str> {
+        let mut host = link.host.as_str();
+        loop {
+            if let Some(domain) = self.domains.get(host) {
+                return Some(domain);
+            }
+            host = host.split_once('.')?.1;
+        }
+    }
+}
+
+/// Scores links against your own allowlist and blocklist of domains. When a
+/// host is covered by both, the more specific entry wins, so you can block a
+/// site but allow one of its subdomains.
+pub struct DomainReputationRule {
+    allow: DomainList,
+    block: DomainList,
+}
+
+impl DomainReputationRule {
+    pub fn new(allow: DomainList, block: DomainList) -> Self {
+        Self { allow, block }
+    }
+
+    fn is_blocked(&self, link: &Link) -> bool {
+        match (self.block.matching(link), self.allow.matching(link)) {
+            (Some(blocked), Some(allowed)) => blocked.len() >= allowed.len(),
+            (blocked, _) => blocked.is_some(),
+        }
+    }
+}
+
+impl Rule for DomainReputationRule {
+    fn name(&self) -> &str {
+        "domain_reputation"
+    }
+
+    fn description(&self) -> &str {
+        "Domains you allowed or blocked"
+    }
+
+    fn weights(&self) -> Vec<i32> {
+        vec![BLOCKED_WEIGHT, ALLOWED_WEIGHT]
+    }
+
+    fn evaluate(&self, post: &PostView) -> Option<RuleHit> {
+        let links = post.links();
+        let blocked: Vec<&Link> = links.iter().filter(|l| self.is_blocked(l)).collect();
+        if let Some(first) = blocked.first() {
+            let label = format!("Links to blocked domain {}", first.domain());
+            let spans = blocked.iter().map(|l| l.span).collect();
+            return Some(RuleHit::new(self.name(), BLOCKED_WEIGHT, label).with_spans(spans));
+        }
+        if !links.is_empty() && links.iter().all(|l| self.allow.matching(l).is_some()) {
+            return Some(RuleHit::new(self.name(), ALLOWED_WEIGHT, "Links to trusted domains"));
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::filter::Scope;
+
+    fn link(markup: &str) -> Link {
+        Link::parse(markup, MatchSpan { scope: Scope::FullText, start: 0, end: markup.len() }).unwrap()
+    }
+
+    #[test]
+    fn test_parse_forms() {
+        assert_eq!(link("[docs](https://Docs.rs/regex)").host, "docs.rs");
+        assert_eq!(link("<https://bit.ly/x>").host, "bit.ly");
+        assert_eq!(link("https://www.pump.fun/abc").domain(), "pump.fun");
+        assert!(link("http://192.168.0.1/claim").ip);
+        assert!(link("https://pаypal.com").is_punycode());
+        assert!(Link::parse("<not-a-url>", MatchSpan { scope: Scope::FullText, start: 0, end: 11 }).is_none());
+    }
+
+    #[test]
+    fn test_domain_list_matches_subdomains() {
+        let list = DomainList::parse("# shills\npump.fun\n*.Discord.gg  # invites\n\n");
+        assert_eq!(list.len(), 2);
+        assert_eq!(list.matching(&link("https://app.pump.fun/x")), Some("pump.fun"));
+        assert_eq!(list.matching(&link("https://discord.gg/abc")), Some("discord.gg"));
+        assert_eq!(list.matching(&link("https://notpump.fun")), None);
+    }
+
+    #[test]
+    fn test_specific_allow_beats_block() {
+        let rule = DomainReputationRule::new(
+            DomainList::parse("docs.example.com"),
+            DomainList::parse("example.com"),
+        );
+        assert!(rule.is_blocked(&link("https://shop.example.com")));
+        assert!(!rule.is_blocked(&link("https://docs.example.com/guide")));
+    }
+}
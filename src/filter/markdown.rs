@@ -0,0 +1,237 @@
+//! Splits post text into markdown regions so rules can skip code and quotes
+
+use serde::{Deserialize, Serialize};
+use std::ops::Range;
+
+/// Kind of markdown text
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Region {
+    /// Ordinary text written by the author
+    Prose,
+    /// Text between backticks
+    InlineCode,
+    /// A fenced code block, fences included
+    CodeBlock,
+    /// A markdown link, autolink or bare URL
+    Link,
+    /// Lines starting with `>`
+    Quote,
+}
+
+impl Region {
+    pub const ALL: [Region; 5] = [Region::Prose, Region::InlineCode, Region::CodeBlock, Region::Link, Region::Quote];
+
+    pub fn is_code(self) -> bool {
+        matches!(self, Region::InlineCode | Region::CodeBlock)
+    }
+}
+
+/// A stretch of text in one region
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Segment {
+    pub region: Region,
+    pub start: usize,
+    pub end: usize,
+    /// Language named after the opening fence of a code block
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub lang: Option<String>,
+}
+
+impl Segment {
+    pub fn range(&self) -> Range<usize> {
+        self.start..self.end
+    }
+}
+
+/// Split text into regions. Every byte belongs to exactly one segment.
+pub fn segments(text: &str) -> Vec<Segment> {
+    let mut out: Vec<Segment> = Vec::new();
+    let mut push = |region: Region, range: Range<usize>, lang: Option<String>| {
+        if range.is_empty() {
+            return;
+        }
+        match out.last_mut() {
+            Some(last) if last.region == region && last.lang == lang && last.end == range.start => {
+                last.end = range.end
+            }
+            _ => out.push(Segment { region, start: range.start, end: range.end, lang }),
+        }
+    };
+
+    // Open fence: its character, its length and the code block's language
+    let mut fence: Option<(char, usize, Option<String>)> = None;
+    let mut offset = 0;
+    for line in text.split_inclusive('\n') {
+        let range = offset..offset + line.len();
+        offset += line.len();
+        let trimmed = line.trim_start();
+
+        if let Some((c, len, lang)) = &fence {
+            let closing = trimmed.chars().take_while(|x| x == c).count();
+            let lang = lang.clone();
+            if closing >= *len && trimmed[closing..].trim().is_empty() {
+                fence = None;
+            }
+            push(Region::CodeBlock, range, lang);
+            continue;
+        }
+        if let Some((c, len)) = fence_start(trimmed) {
+            let lang = trimmed[len..].split_whitespace().next().map(str::to_string);
+            fence = Some((c, len, lang.clone()));
+            push(Region::CodeBlock, range, lang);
+            continue;
+        }
+        if trimmed.starts_with('>') {
+            push(Region::Quote, range, None);
+            continue;
+        }
+        for (region, inline) in inline_segments(line) {
+            push(region, range.start + inline.start..range.start + inline.end, None);
+        }
+    }
+    out
+}
+
+/// Fence character and length if the line opens a code block
+fn fence_start(line: &str) -> Option<(char, usize)> {
+    let c = line.chars().next().filter(|c| *c == '`' || *c == '~')?;
+    let len = line.chars().take_while(|x| *x == c).count();
+    // A backtick fence's info string can't contain backticks
+    let valid = len >= 3 && !(c == '`' && line[len..].contains('`'));
+    valid.then_some((c, len))
+}
+
+/// Inline code, links and prose within one line
+fn inline_segments(line: &str) -> Vec<(Region, Range<usize>)> {
+    let mut out = Vec::new();
+    let bytes = line.as_bytes();
+    let mut prose_start = 0;
+    let mut i = 0;
+    while i < line.len() {
+        let found = match bytes[i] {
+            b'`' => code_span(line, i).map(|end| (Region::InlineCode, end)),
+            b'[' => link_end(line, i).map(|end| (Region::Link, end)),
+            b'<' => autolink_end(line, i).map(|end| (Region::Link, end)),
+            b'h' if (i == 0 || !line[..i].ends_with(|c: char| c.is_alphanumeric()))
+                && (line[i..].starts_with("http://") || line[i..].starts_with("https://")) =>
+            {
+                let end = line[i..].find(char::is_whitespace).map_or(line.len(), |n| i + n);
+                // Trailing punctuation ends the sentence, not the URL
+                let end = i + line[i..end].trim_end_matches(['.', ',', ')', '!', '?', ';', ':']).len();
+                Some((Region::Link, end))
+            }
+            _ => None,
+        };
+        match found {
+            Some((region, end)) => {
+                out.push((Region::Prose, prose_start..i));
+                out.push((region, i..end));
+                i = end;
+                prose_start = end;
+            }
+            None => i += line[i..].chars().next().map_or(1, char::len_utf8),
+        }
+    }
+    out.push((Region::Prose, prose_start..line.len()));
+    out
+}
+
+/// End of a code span opened by the backticks at `start`
+fn code_span(line: &str, start: usize) -> Option<usize> {
+    let ticks = line[start..].bytes().take_while(|b| *b == b'`').count();
+    let mut i = start + ticks;
+    while let Some(n) = line[i..].find('`') {
+        let run_start = i + n;
+        let run = line[run_start..].bytes().take_while(|b| *b == b'`').count();
+        if run == ticks {
+            return Some(run_start + run);
+        }
+        i = run_start + run;
+    }
+    None
+}
+
+/// End of a `[text](url)` link starting at `start`
+fn link_end(line: &str, start: usize) -> Option<usize> {
+    let close = start + line[start..].find("](")?;
+    if line[start + 1..close].contains('[') {
+        return None;
+    }
+    let paren = close + 2;
+    let end = paren + line[paren..].find(')')?;
+    (!line[paren..end].contains(char::is_whitespace)).then_some(end + 1)
+}
+
+/// End of a `<https://...>` autolink starting at `start`
+fn autolink_end(line: &str, start: usize) -> Option<usize> {
+    let rest = &line[start + 1..];
+    if !(rest.starts_with("http://") || rest.starts_with("https://")) {
+        return None;
+    }
+    let end = start + 1 + rest.find('>')?;
+    (!line[start..end].contains(char::is_whitespace)).then_some(end + 1)
+}
+
+/// Blank out the text outside `regions`, keeping byte offsets and line breaks
+pub fn mask(text: &str, segments: &[Segment], regions: &[Region]) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut last = 0;
+    for segment in segments.iter().filter(|s| !regions.contains(&s.region)) {
+        out.push_str(&text[last..segment.start]);
+        for c in text[segment.range()].chars() {
+            if c == '\n' {
+                out.push('\n');
+            } else {
+                out.extend(std::iter::repeat_n(' ', c.len_utf8()));
+            }
+        }
+        last = segment.end;
+    }
+    out.push_str(&text[last..]);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn regions(text: &str) -> Vec<(Region, &str)> {
+        segments(text).into_iter().map(|s| (s.region, &text[s.range()])).collect()
+    }
+
+    #[test]
+    fn test_segments() {
+        let text = "Try `cargo build` or see [docs](https://x.dev).\n> buy CLAW\n```rust\nlet token = 1;\n```\nThanks https://a.b/c.";
+        assert_eq!(
+            regions(text),
+            vec![
+                (Region::Prose, "Try "),
+                (Region::InlineCode, "`cargo build`"),
+                (Region::Prose, " or see "),
+                (Region::Link, "[docs](https://x.dev)"),
+                (Region::Prose, ".\n"),
+                (Region::Quote, "> buy CLAW\n"),
+                (Region::CodeBlock, "```rust\nlet token = 1;\n```\n"),
+                (Region::Prose, "Thanks "),
+                (Region::Link, "https://a.b/c"),
+                (Region::Prose, "."),
+            ]
+        );
+        assert_eq!(segments(text)[6].lang.as_deref(), Some("rust"));
+    }
+
+    #[test]
+    fn test_unclosed_markup_is_prose() {
+        assert_eq!(regions("a `b and [c] (d)"), vec![(Region::Prose, "a `b and [c] (d)")]);
+        assert_eq!(regions("```\nnever closed"), vec![(Region::CodeBlock, "```\nnever closed")]);
+    }
+
+    #[test]
+    fn test_mask_keeps_offsets() {
+        let text = "buy `é` now";
+        let masked = mask(text, &segments(text), &[Region::Prose]);
+        assert_eq!(masked.len(), text.len());
+        assert_eq!(masked, "buy      now");
+    }
+}
@@ -0,0 +1,293 @@
+//! Text normalization applied before any rule sees a post
+//!
+//! Undoes the usual tricks for slipping past regex rules: fullwidth and
+//! styled letters (NFKC), lookalike letters from other scripts, zero-width
+//! characters, letters spaced apart (`C L A W`) and leetspeak (`m1nt`).
+
+use std::ops::Range;
+use unicode_normalization::UnicodeNormalization;
+
+/// A text after normalization
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Normalized {
+    pub text: String,
+    /// Characters that were disguised rather than innocently formatted
+    pub suspicious: usize,
+    /// Words spelled out from leetspeak or spaced letters. Plenty of them are
+    /// innocent ("k3s", "U.S.A"), so they only count as disguised once a rule
+    /// matches the spelled-out word.
+    pub folded: Vec<Folded>,
+}
+
+/// A word spelled out from leetspeak or spaced letters
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Folded {
+    /// Byte range of the word in the normalized text
+    pub start: usize,
+    pub end: usize,
+    /// Characters replaced or removed to spell it
+    pub changed: usize,
+}
+
+/// Normalize a text and count the disguised characters in it
+pub fn normalize(raw: &str) -> Normalized {
+    // NFKC also folds harmless typography like "…" and no-break spaces, so
+    // only styled letters count as suspicious
+    let mut suspicious = raw.chars().filter(|&c| is_styled_letter(c)).count();
+    let chars: Vec<char> = raw.nfkc().collect();
+
+    let (chars, hidden) = strip_invisible(&chars);
+    suspicious += hidden;
+    let (chars, collapsed) = collapse_spacing(&chars);
+
+    let mut text = String::with_capacity(chars.len());
+    let mut folded = Vec::new();
+    // Byte offset in `text` of each character, to place the collapsed words
+    let mut offsets = Vec::with_capacity(chars.len() + 1);
+    let mut word = Vec::new();
+    for c in chars.into_iter().chain(std::iter::once(' ')) {
+        if c.is_alphanumeric() || leet_letter(c).is_some() {
+            word.push(c);
+            continue;
+        }
+        let (lookalikes, leet) = fold_word(&mut word);
+        suspicious += lookalikes;
+        let start = text.len();
+        for c in word.drain(..).chain(std::iter::once(c)) {
+            offsets.push(text.len());
+            text.push(c);
+        }
+        if leet > 0 {
+            folded.push(Folded { start, end: text.len() - 1, changed: leet });
+        }
+    }
+    text.pop();
+
+    folded.extend(collapsed.into_iter().map(|(range, removed)| Folded {
+        start: offsets[range.start],
+        end: offsets[range.end],
+        changed: removed,
+    }));
+    folded.sort_by_key(|f| f.start);
+
+    Normalized { text, suspicious, folded }
+}
+
+/// Fullwidth, mathematical and enclosed letters and digits
+fn is_styled_letter(c: char) -> bool {
+    matches!(c as u32,
+        0xFF10..=0xFF19 | 0xFF21..=0xFF3A | 0xFF41..=0xFF5A // Fullwidth
+        | 0x1D400..=0x1D7FF                                  // Mathematical alphanumerics
+        | 0x2460..=0x24FF                                    // Enclosed alphanumerics
+        | 0x1F130..=0x1F189)                                 // Squared letters
+}
+
+pub(crate) fn is_invisible(c: char) -> bool {
+    matches!(c, '\u{200B}' | '\u{200C}' | '\u{200D}' | '\u{2060}' | '\u{FEFF}' | '\u{00AD}' | '\u{180E}')
+}
+
+/// Drop invisible characters next to letters; joiners between emoji are kept.
+/// Returns the characters and how many invisibles sat inside a word.
+fn strip_invisible(chars: &[char]) -> (Vec<char>, usize) {
+    let mut kept = Vec::with_capacity(chars.len());
+    let mut hidden = 0;
+    for (i, &c) in chars.iter().enumerate() {
+        if !is_invisible(c) {
+            kept.push(c);
+            continue;
+        }
+        let before = i > 0 && chars[i - 1].is_alphanumeric();
+        let after = chars.get(i + 1).is_some_and(|n| n.is_alphanumeric());
+        if before && after {
+            hidden += 1;
+        }
+        if !before && !after {
+            kept.push(c);
+        }
+    }
+    (kept, hidden)
+}
+
+fn is_spacer(c: char) -> bool {
+    matches!(c, ' ' | '.' | '-' | '_' | '*' | '·')
+}
+
+/// Join three or more single letters separated by single spacers, as in
+/// `C L A W` or `m.i.n.t`. Returns the characters, and the range of each
+/// joined word in them with the number of spacers removed from it.
+fn collapse_spacing(chars: &[char]) -> (Vec<char>, Vec<(Range<usize>, usize)>) {
+    let single = |i: usize| {
+        chars[i].is_alphabetic()
+            && (i == 0 || !chars[i - 1].is_alphanumeric())
+            && chars.get(i + 1).is_none_or(|n| !n.is_alphanumeric())
+    };
+
+    let mut out = Vec::with_capacity(chars.len());
+    let mut joined = Vec::new();
+    let mut i = 0;
+    while i < chars.len() {
+        if single(i) {
+            let mut end = i;
+            while end + 2 < chars.len() && is_spacer(chars[end + 1]) && single(end + 2) {
+                end += 2;
+            }
+            let letters = (end - i) / 2 + 1;
+            if letters >= 3 {
+                let start = out.len();
+                out.extend(chars[i..=end].iter().step_by(2));
+                joined.push((start..out.len(), letters - 1));
+                i = end + 1;
+                continue;
+            }
+        }
+        out.push(chars[i]);
+        i += 1;
+    }
+    (out, joined)
+}
+
+/// Latin letter a Cyrillic or Greek lookalike stands for
+fn confusable(c: char) -> Option<char> {
+    Some(match c {
+        'А' | 'Α' => 'A',
+        'В' | 'Β' => 'B',
+        'С' => 'C',
+        'Е' | 'Ε' => 'E',
+        'Ζ' => 'Z',
+        'Н' | 'Η' => 'H',
+        'І' | 'Ι' => 'I',
+        'Ј' => 'J',
+        'К' | 'Κ' => 'K',
+        'М' | 'Μ' => 'M',
+        'Ν' => 'N',
+        'О' | 'Ο' => 'O',
+        'Р' | 'Ρ' => 'P',
+        'Ԛ' => 'Q',
+        'Ѕ' => 'S',
+        'Т' | 'Τ' => 'T',
+        'У' | 'Υ' => 'Y',
+        'Ԝ' => 'W',
+        'Х' | 'Χ' => 'X',
+        'а' | 'α' => 'a',
+        'с' => 'c',
+        'ԁ' => 'd',
+        'е' => 'e',
+        'һ' => 'h',
+        'і' | 'ι' => 'i',
+        'ј' => 'j',
+        'κ' => 'k',
+        'о' | 'ο' => 'o',
+        'р' | 'ρ' => 'p',
+        'ԛ' => 'q',
+        'ѕ' => 's',
+        'ν' => 'v',
+        'ԝ' => 'w',
+        'х' => 'x',
+        'у' => 'y',
+        _ => return None,
+    })
+}
+
+/// Lowercase letter a leetspeak character stands for
+fn leet_letter(c: char) -> Option<char> {
+    Some(match c {
+        '0' => 'o',
+        '1' => 'i',
+        '3' => 'e',
+        '4' => 'a',
+        '5' => 's',
+        '7' => 't',
+        '$' => 's',
+        _ => return None,
+    })
+}
+
+/// Undo lookalikes in mixed-script words and leetspeak between letters.
+/// Returns the number of lookalikes and of leetspeak characters changed.
+fn fold_word(word: &mut [char]) -> (usize, usize) {
+    let mut lookalikes = 0;
+
+    // A word written entirely in another script is just that script
+    if word.iter().any(|c| c.is_ascii_alphabetic()) {
+        for c in word.iter_mut() {
+            if let Some(latin) = confusable(*c) {
+                *c = latin;
+                lookalikes += 1;
+            }
+        }
+    }
+
+    // Digits only stand for letters with letters on both sides, so "m1nt" and
+    // "t00ken" fold while "gpt4", "1000x" and "$CLAW" stay as they are
+    let letter_at = |word: &[char], from: usize, step: isize| {
+        let mut i = from as isize + step;
+        while i >= 0 && (i as usize) < word.len() {
+            let c = word[i as usize];
+            if leet_letter(c).is_none() {
+                return c.is_alphabetic().then_some(c);
+            }
+            i += step;
+        }
+        None
+    };
+    let mut leet = 0;
+    for i in 0..word.len() {
+        let Some(letter) = leet_letter(word[i]) else { continue };
+        let (Some(before), Some(_)) = (letter_at(word, i, -1), letter_at(word, i, 1)) else { continue };
+        word[i] = if before.is_uppercase() { letter.to_ascii_uppercase() } else { letter };
+        leet += 1;
+    }
+
+    (lookalikes, leet)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_plain_text_is_untouched() {
+        let text = "Version 1.2 of gpt4 costs $5 — 1000x better… see web3.";
+        let normalized = normalize(text);
+        assert_eq!(normalized.suspicious, 0);
+        assert_eq!(normalized.text, text.nfkc().collect::<String>());
+    }
+
+    #[test]
+    fn test_disguises_are_undone() {
+        assert_eq!(normalize("Buy \u{0421} L A W").text, "Buy CLAW");
+        assert_eq!(normalize("ＣＬＡＷ").text, "CLAW");
+        assert_eq!(normalize("to\u{200B}ken").text, "token");
+        assert_eq!(normalize("m1nt your T0KEN").text, "mint your TOKEN");
+        assert_eq!(normalize("t00ken").text, "tooken");
+    }
+
+    #[test]
+    fn test_suspicious_counts() {
+        assert_eq!(normalize("\u{0421}LAW").suspicious, 1);
+        assert_eq!(normalize("ＣＬＡＷ").suspicious, 4);
+        assert_eq!(normalize("C L A W").suspicious, 0);
+    }
+
+    #[test]
+    fn test_folded_words() {
+        let normalized = normalize("Buy C L A W, m1nt t00ken");
+        assert_eq!(normalized.text, "Buy CLAW, mint tooken");
+        let words: Vec<(&str, usize)> = normalized
+            .folded
+            .iter()
+            .map(|f| (&normalized.text[f.start..f.end], f.changed))
+            .collect();
+        assert_eq!(words, [("CLAW", 3), ("mint", 1), ("tooken", 2)]);
+
+        let normalized = normalize("Привет C.L.A.W");
+        assert_eq!(&normalized.text[normalized.folded[0].start..normalized.folded[0].end], "CLAW");
+    }
+
+    #[test]
+    fn test_other_scripts_are_kept() {
+        let russian = "Привет, как дела?";
+        assert_eq!(normalize(russian).text, russian);
+        assert_eq!(normalize(russian).suspicious, 0);
+    }
+}
@@ -0,0 +1,45 @@
+//! Score adjustments from the author reputation store
+
+use std::collections::HashMap;
+
+use super::rule::{PostView, Rule, RuleHit};
+
+/// Score change for an author you allowed
+pub const ALLOW_WEIGHT: i32 = 20;
+
+/// Score change for an author you denied
+pub const DENY_WEIGHT: i32 = -40;
+
+/// Rewards authors with a good record or an allow mark and penalizes those
+/// with a spam record or a deny mark
+pub struct AuthorReputationRule {
+    /// Delta and label per lowercased author name
+    authors: HashMap<String, (i32, String)>,
+}
+
+impl AuthorReputationRule {
+    /// `authors` holds the score change and its label per lowercased author
+    /// name, as in [`AuthorStore::reputations`](crate::corpus::AuthorStore::reputations)
+    pub fn new(authors: HashMap<String, (i32, String)>) -> Self {
+        Self { authors }
+    }
+}
+
+impl Rule for AuthorReputationRule {
+    fn name(&self) -> &str {
+        "author_reputation"
+    }
+
+    fn description(&self) -> &str {
+        "Author track records and manual marks"
+    }
+
+    fn weights(&self) -> Vec<i32> {
+        vec![DENY_WEIGHT, ALLOW_WEIGHT]
+    }
+
+    fn evaluate(&self, post: &PostView) -> Option<RuleHit> {
+        let (delta, label) = self.authors.get(&post.author()?.to_lowercase())?;
+        Some(RuleHit::new(self.name(), *delta, label.clone()))
+    }
+}
@@ -0,0 +1,325 @@
+use serde::Serialize;
+use std::borrow::Cow;
+
+use super::injection::{self, InjectionFinding};
+use super::links::Link;
+use super::markdown::{self, Region, Segment};
+use super::normalize::{normalize, Folded};
+use super::{ContentKind, Scope};
+
+/// The post or comment as seen by rules.
+///
+/// Title and content are split into markdown regions (see [`Region`]) and
+/// everything but code is normalized (see [`normalize`]), so rules match
+/// `С L A W` and `m1nt` like `CLAW` and `mint`, and match spans point into the
+/// normalized text. The text as written is kept in [`PostView::raw`].
+#[derive(Debug, Clone)]
+pub struct PostView {
+    kind: ContentKind,
+    title: String,
+    content: String,
+    author: Option<String>,
+    full_text: String,
+    raw_title: String,
+    raw_content: String,
+    suspicious: [usize; 2],
+    folded: [Vec<Folded>; 2],
+    segments: [Vec<Segment>; 2],
+    links: Vec<Link>,
+    injection: Vec<InjectionFinding>,
+    parent: Option<Box<PostView>>,
+}
+
+/// A text after markdown splitting and normalization
+struct Prepared {
+    text: String,
+    segments: Vec<Segment>,
+    suspicious: usize,
+    folded: Vec<Folded>,
+    links: Vec<Link>,
+}
+
+impl Prepared {
+    fn new(raw: &str) -> Self {
+        let mut text = String::with_capacity(raw.len());
+        let mut segments = Vec::new();
+        let mut suspicious = 0;
+        let mut folded = Vec::new();
+        let mut links = Vec::new();
+        for segment in markdown::segments(raw) {
+            let start = text.len();
+            let piece = &raw[segment.range()];
+            // URLs are read as written, since normalizing would change hosts
+            // and read ids like "dQw4w9WgXcQ" as leetspeak
+            if segment.region.is_code() || segment.region == Region::Link {
+                text.push_str(piece);
+            } else {
+                let normalized = normalize(piece);
+                suspicious += normalized.suspicious;
+                folded.extend(normalized.folded.into_iter().map(|f| Folded {
+                    start: f.start + start,
+                    end: f.end + start,
+                    ..f
+                }));
+                text.push_str(&normalized.text);
+            }
+            if segment.region == Region::Link {
+                let span = MatchSpan { scope: Scope::FullText, start, end: text.len() };
+                links.extend(Link::parse(piece, span));
+            }
+            segments.push(Segment { start, end: text.len(), ..segment });
+        }
+        Self { text, segments, suspicious, folded, links }
+    }
+}
+
+impl PostView {
+    pub fn new(title: &str, content: &str, author: Option<&str>) -> Self {
+        Self::build(ContentKind::Post, title, content, author)
+    }
+
+    /// A comment, which has no title
+    pub fn comment(content: &str, author: Option<&str>) -> Self {
+        Self::build(ContentKind::Comment, "", content, author)
+    }
+
+    fn build(kind: ContentKind, title: &str, content: &str, author: Option<&str>) -> Self {
+        let title_text = Prepared::new(title);
+        let content_text = Prepared::new(content);
+        let full_text = match kind {
+            ContentKind::Post => format!("{} {}", title_text.text, content_text.text),
+            ContentKind::Comment => content_text.text.clone(),
+        };
+        // Link spans point into the full text, where content follows the title
+        let shift = match kind {
+            ContentKind::Post => title_text.text.len() + 1,
+            ContentKind::Comment => 0,
+        };
+        let links = title_text
+            .links
+            .into_iter()
+            .chain(content_text.links.into_iter().map(|mut link| {
+                link.span.start += shift;
+                link.span.end += shift;
+                link
+            }))
+            .collect();
+        // Injections are looked for in the text as written, since decoding
+        // base64 or reading tag characters needs them untouched
+        let mut injection = injection::scan(title, Scope::Title);
+        injection.extend(injection::scan(content, Scope::Content));
+        Self {
+            kind,
+            title: title_text.text,
+            content: content_text.text,
+            author: author.map(str::to_string),
+            full_text,
+            raw_title: title.to_string(),
+            raw_content: content.to_string(),
+            suspicious: [title_text.suspicious, content_text.suspicious],
+            folded: [title_text.folded, content_text.folded],
+            segments: [title_text.segments, content_text.segments],
+            links,
+            injection,
+            parent: None,
+        }
+    }
+
+    /// Attach the post a comment replies to
+    pub fn with_parent(mut self, parent: PostView) -> Self {
+        self.parent = Some(Box::new(parent));
+        self
+    }
+
+    pub fn kind(&self) -> ContentKind {
+        self.kind
+    }
+
+    /// The post this comment replies to, if known
+    pub fn parent(&self) -> Option<&PostView> {
+        self.parent.as_deref()
+    }
+
+    pub fn title(&self) -> &str {
+        &self.title
+    }
+
+    pub fn content(&self) -> &str {
+        &self.content
+    }
+
+    pub fn author(&self) -> Option<&str> {
+        self.author.as_deref()
+    }
+
+    /// Title and content joined by a space
+    pub fn full_text(&self) -> &str {
+        &self.full_text
+    }
+
+    /// The text a rule with the given scope should look at
+    pub fn scope(&self, scope: Scope) -> &str {
+        match scope {
+            Scope::Title => &self.title,
+            Scope::Content => &self.content,
+            Scope::FullText => &self.full_text,
+        }
+    }
+
+    /// Markdown regions of the scoped text
+    pub fn segments(&self, scope: Scope) -> Vec<Segment> {
+        match scope {
+            Scope::Title => self.segments[0].clone(),
+            Scope::Content => self.segments[1].clone(),
+            Scope::FullText if self.kind == ContentKind::Comment => self.segments[1].clone(),
+            Scope::FullText => {
+                // Content follows the title and the space joining them
+                let shift = self.title.len() + 1;
+                let content = self.segments[1].iter().map(|s| Segment {
+                    start: s.start + shift,
+                    end: s.end + shift,
+                    ..s.clone()
+                });
+                self.segments[0].iter().cloned().chain(content).collect()
+            }
+        }
+    }
+
+    /// Whether the content has prose of the author's own, not just quotes,
+    /// code or links
+    pub fn has_own_prose(&self) -> bool {
+        self.segments[1]
+            .iter()
+            .any(|s| s.region == Region::Prose && !self.content[s.start..s.end].trim().is_empty())
+    }
+
+    /// The scoped text with everything outside `regions` blanked to spaces,
+    /// so offsets still line up with [`PostView::scope`]
+    pub fn scope_in(&self, scope: Scope, regions: &[Region]) -> Cow<'_, str> {
+        let segments = self.segments(scope);
+        if segments.iter().all(|s| regions.contains(&s.region)) {
+            Cow::Borrowed(self.scope(scope))
+        } else {
+            Cow::Owned(markdown::mask(self.scope(scope), &segments, regions))
+        }
+    }
+
+    /// Links in the title and content, with spans into the full text
+    pub fn links(&self) -> &[Link] {
+        &self.links
+    }
+
+    /// Prompt injections in the title and content, with spans into the text
+    /// as written
+    pub fn injection(&self) -> &[InjectionFinding] {
+        &self.injection
+    }
+
+    /// Title or content as written, before normalization
+    pub fn raw(&self, scope: Scope) -> String {
+        match scope {
+            Scope::Title => self.raw_title.clone(),
+            Scope::Content => self.raw_content.clone(),
+            Scope::FullText if self.kind == ContentKind::Comment => self.raw_content.clone(),
+            Scope::FullText => format!("{} {}", self.raw_title, self.raw_content),
+        }
+    }
+
+    /// Number of disguised characters normalization undid in the scoped
+    /// text, not counting the [folded](PostView::folded) words
+    pub fn suspicious(&self, scope: Scope) -> usize {
+        match scope {
+            Scope::Title => self.suspicious[0],
+            Scope::Content => self.suspicious[1],
+            Scope::FullText => self.suspicious[0] + self.suspicious[1],
+        }
+    }
+
+    /// Words spelled out from leetspeak or spaced letters, with offsets into
+    /// the scoped text
+    pub fn folded(&self, scope: Scope) -> Vec<Folded> {
+        match scope {
+            Scope::Title => self.folded[0].clone(),
+            Scope::Content => self.folded[1].clone(),
+            Scope::FullText if self.kind == ContentKind::Comment => self.folded[1].clone(),
+            Scope::FullText => {
+                let shift = self.title.len() + 1;
+                let content = self.folded[1].iter().map(|f| Folded {
+                    start: f.start + shift,
+                    end: f.end + shift,
+                    ..*f
+                });
+                self.folded[0].iter().copied().chain(content).collect()
+            }
+        }
+    }
+
+    /// The same span with offsets into the full text
+    pub fn in_full_text(&self, span: MatchSpan) -> MatchSpan {
+        let shift = match (span.scope, self.kind) {
+            (Scope::Content, ContentKind::Post) => self.title.len() + 1,
+            _ => 0,
+        };
+        MatchSpan { scope: Scope::FullText, start: span.start + shift, end: span.end + shift }
+    }
+}
+
+/// Byte range of a match within the text of `scope`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct MatchSpan {
+    pub scope: Scope,
+    pub start: usize,
+    pub end: usize,
+}
+
+/// What a rule contributes to a post's score
+#[derive(Debug, Clone, Serialize)]
+pub struct RuleHit {
+    pub rule: String,
+    pub delta: i32,
+    pub label: String,
+    pub spans: Vec<MatchSpan>,
+}
+
+impl RuleHit {
+    pub fn new(rule: &str, delta: i32, label: impl Into<String>) -> Self {
+        Self {
+            rule: rule.to_string(),
+            delta,
+            label: label.into(),
+            spans: Vec::new(),
+        }
+    }
+
+    pub fn with_spans(mut self, spans: Vec<MatchSpan>) -> Self {
+        self.spans = spans;
+        self
+    }
+}
+
+/// A scoring signal. Negative deltas are reported as flags, positive ones as
+/// positive signals.
+pub trait Rule: Send + Sync {
+    /// Unique name of the rule
+    fn name(&self) -> &str;
+
+    /// Score the post, or `None` if the rule doesn't apply
+    fn evaluate(&self, post: &PostView) -> Option<RuleHit>;
+
+    /// Score the post once every rule has been evaluated, for rules that
+    /// depend on what the others matched; they return `None` from
+    /// [`Rule::evaluate`]
+    fn evaluate_after(&self, _post: &PostView, _hits: &[RuleHit]) -> Option<RuleHit> {
+        None
+    }
+
+    /// Summary shown by the `rules` command
+    fn description(&self) -> &str {
+        self.name()
+    }
+
+    /// Possible score deltas, shown by the `rules` command
+    fn weights(&self) -> Vec<i32> {
+        Vec::new()
+    }
+}
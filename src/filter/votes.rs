@@ -0,0 +1,51 @@
+//! Score adjustments learned from your own votes
+
+use std::collections::HashMap;
+
+use super::rule::{PostView, Rule, RuleHit};
+
+/// Score change per net vote on an author
+const WEIGHT_PER_VOTE: i32 = 10;
+
+/// Largest adjustment for a single author
+const MAX_WEIGHT: i32 = 30;
+
+/// Rewards authors you upvote and penalizes those you downvote
+pub struct AuthorVotesRule {
+    balance: HashMap<String, i32>,
+}
+
+impl AuthorVotesRule {
+    /// `balance` holds your upvotes minus downvotes per lowercased author name,
+    /// as in [`VoteHistory::author_balance`](crate::corpus::VoteHistory::author_balance)
+    pub fn new(balance: HashMap<String, i32>) -> Self {
+        Self { balance }
+    }
+}
+
+impl Rule for AuthorVotesRule {
+    fn name(&self) -> &str {
+        "author_votes"
+    }
+
+    fn description(&self) -> &str {
+        "Authors you voted on"
+    }
+
+    fn weights(&self) -> Vec<i32> {
+        vec![-MAX_WEIGHT, MAX_WEIGHT]
+    }
+
+    fn evaluate(&self, post: &PostView) -> Option<RuleHit> {
+        let author = post.author()?;
+        let balance = *self.balance.get(&author.to_lowercase()).filter(|b| **b != 0)?;
+        let delta = (balance * WEIGHT_PER_VOTE).clamp(-MAX_WEIGHT, MAX_WEIGHT);
+        let label = match balance {
+            1 => format!("You upvoted {}", author),
+            -1 => format!("You downvoted {}", author),
+            b if b > 0 => format!("You upvoted {} ({} net votes)", author, b),
+            b => format!("You downvoted {} ({} net votes)", author, -b),
+        };
+        Some(RuleHit::new(self.name(), delta, label))
+    }
+}
@@ -0,0 +1,96 @@
+//! JSONL files with one record per line
+//!
+//! The corpus, vote history and author store are kept this way, and so are the
+//! dedup window, flood history and profile cache. The helpers live outside
+//! [`corpus`](crate::corpus) so `filter` and `moltbook` can use them without
+//! depending on it.
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::fmt;
+use std::io::Write;
+use std::path::Path;
+
+/// Error raised while reading or writing a data file
+#[derive(Debug)]
+pub struct DataFileError {
+    pub line: Option<usize>,
+    pub message: String,
+}
+
+impl fmt::Display for DataFileError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.line {
+            Some(line) => write!(f, "line {}: {}", line, self.message),
+            None => write!(f, "{}", self.message),
+        }
+    }
+}
+
+impl std::error::Error for DataFileError {}
+
+/// Parse one JSON object per non-blank line
+pub(crate) fn parse_lines<T: DeserializeOwned>(src: &str) -> Result<Vec<T>, DataFileError> {
+    src.lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(i, line)| {
+            serde_json::from_str(line).map_err(|e| DataFileError {
+                line: Some(i + 1),
+                message: e.to_string(),
+            })
+        })
+        .collect()
+}
+
+/// Read and parse a file; a missing file has no records
+pub(crate) fn read_lines<T: DeserializeOwned>(path: &Path) -> Result<Vec<T>, DataFileError> {
+    match std::fs::read_to_string(path) {
+        Ok(src) => parse_lines(&src),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Vec::new()),
+        Err(e) => Err(DataFileError {
+            line: None,
+            message: format!("failed to read {}: {}", path.display(), e),
+        }),
+    }
+}
+
+/// Write each item as one JSON line at the end of a file, creating it and its directory if needed
+pub(crate) fn append_lines<T: Serialize>(path: &Path, items: &[T]) -> Result<(), DataFileError> {
+    let io_error = |e: std::io::Error| DataFileError {
+        line: None,
+        message: format!("failed to write {}: {}", path.display(), e),
+    };
+
+    if let Some(dir) = path.parent().filter(|d| !d.as_os_str().is_empty()) {
+        std::fs::create_dir_all(dir).map_err(io_error)?;
+    }
+    let mut file = std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .map_err(io_error)?;
+    for item in items {
+        let line = serde_json::to_string(item).expect("records are serializable");
+        writeln!(file, "{}", line).map_err(io_error)?;
+    }
+    Ok(())
+}
+
+/// Replace a file with one JSON line per item, creating its directory if needed
+pub(crate) fn write_lines<T: Serialize>(path: &Path, items: &[T]) -> Result<(), DataFileError> {
+    let io_error = |e: std::io::Error| DataFileError {
+        line: None,
+        message: format!("failed to write {}: {}", path.display(), e),
+    };
+
+    if let Some(dir) = path.parent().filter(|d| !d.as_os_str().is_empty()) {
+        std::fs::create_dir_all(dir).map_err(io_error)?;
+    }
+    let mut out = String::new();
+    for item in items {
+        out.push_str(&serde_json::to_string(item).expect("records are serializable"));
+        out.push('\n');
+    }
+    std::fs::write(path, out).map_err(io_error)
+}
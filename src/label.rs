@@ -0,0 +1,156 @@
+//! Interactive prompts for the `label` command
+
+use colored::*;
+use moltbook_filter::corpus::Label;
+use moltbook_filter::filter::SpamAnalysis;
+use moltbook_filter::moltbook::Post;
+use serde::Serialize;
+use std::io::{self, BufRead, Write};
+
+use crate::output;
+
+/// Longest stretch of post content shown while labeling
+const PREVIEW_CHARS: usize = 600;
+
+/// What the user answered for one post
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Choice {
+    /// Label the post, optionally with a category
+    Label(Label, Option<String>),
+    /// Set the category for the label that follows
+    Category(String),
+    Skip,
+    Quit,
+}
+
+/// What to do with a post once any pending category is applied
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Answer {
+    Label(Label, Option<String>),
+    Skip,
+    Quit,
+}
+
+/// Parse one answer: `s`/`h` with an optional category, `c <category>`, `k` or empty to skip, `q`
+pub fn parse_choice(line: &str) -> Option<Choice> {
+    let line = line.trim();
+    let (key, rest) = match line.split_once(char::is_whitespace) {
+        Some((key, rest)) => (key, Some(rest.trim().to_string()).filter(|r| !r.is_empty())),
+        None => (line, None),
+    };
+
+    match (key.to_lowercase().as_str(), rest) {
+        ("s" | "spam", category) => Some(Choice::Label(Label::Spam, category)),
+        ("h" | "ham", category) => Some(Choice::Label(Label::Ham, category)),
+        ("c" | "category", Some(category)) => Some(Choice::Category(category)),
+        ("" | "k" | "skip", None) => Some(Choice::Skip),
+        ("q" | "quit", None) => Some(Choice::Quit),
+        _ => None,
+    }
+}
+
+/// Counts for one labeling session
+#[derive(Debug, Default, Serialize)]
+pub struct Session {
+    pub spam: usize,
+    pub ham: usize,
+    pub skipped: usize,
+    /// Posts left out because the corpus already had them
+    pub already_labeled: usize,
+}
+
+/// Print a line of the interactive session; on stderr when stdout carries machine-readable output
+fn say(message: impl std::fmt::Display) {
+    if output::is_human() {
+        println!("{}", message);
+    } else {
+        eprintln!("{}", message);
+    }
+}
+
+/// Show a post with its analysis
+pub fn show(post: &Post, analysis: &SpamAnalysis, position: usize, total: usize) {
+    say(format!("\n{}", "━".repeat(60).dimmed()));
+    let verdict = if analysis.is_spam {
+        format!("🚫 SPAM {}", analysis.score).red()
+    } else {
+        format!("✓ {}", analysis.score).green()
+    };
+    say(format!("({}/{}) {} {}", position, total, verdict, post.title.bold()));
+    say(format!(
+        "    by {} in m/{} [id:{}]",
+        post.author.as_deref().unwrap_or("unknown").cyan(),
+        post.submolt.as_deref().unwrap_or("?"),
+        post.id.dimmed()
+    ));
+
+    let mut preview: String = post.content.chars().take(PREVIEW_CHARS).collect();
+    if preview.len() < post.content.len() {
+        preview.push('…');
+    }
+    say(format!("\n{}\n", preview));
+
+    if !analysis.flags.is_empty() {
+        say(format!("    Flags: {}", analysis.flags.join(", ").dimmed()));
+    }
+    if !analysis.positive_signals.is_empty() {
+        say(format!("    Signals: {}", analysis.positive_signals.join(", ").dimmed()));
+    }
+}
+
+/// Ask until the user gives a label, a skip or quit. End of input counts as quit.
+pub fn ask(input: &mut impl BufRead) -> io::Result<Answer> {
+    let mut category: Option<String> = None;
+    loop {
+        let pending = category.as_deref().map(|c| format!(" [category: {}]", c)).unwrap_or_default();
+        let prompt = format!("[s]pam / [h]am / [k] skip / [c]ategory <name> / [q]uit{} > ", pending);
+        if output::is_human() {
+            print!("{}", prompt.cyan());
+            io::stdout().flush()?;
+        } else {
+            eprint!("{}", prompt);
+            io::stderr().flush()?;
+        }
+
+        let mut line = String::new();
+        if input.read_line(&mut line)? == 0 {
+            return Ok(Answer::Quit);
+        }
+        match parse_choice(&line) {
+            Some(Choice::Category(name)) => category = Some(name),
+            Some(Choice::Label(label, given)) => return Ok(Answer::Label(label, given.or(category))),
+            Some(Choice::Skip) => return Ok(Answer::Skip),
+            Some(Choice::Quit) => return Ok(Answer::Quit),
+            None => say(format!("{}", "Unrecognized answer".yellow())),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_choice() {
+        assert_eq!(parse_choice("s\n"), Some(Choice::Label(Label::Spam, None)));
+        assert_eq!(
+            parse_choice("H  meta "),
+            Some(Choice::Label(Label::Ham, Some("meta".to_string())))
+        );
+        assert_eq!(parse_choice("c crypto"), Some(Choice::Category("crypto".to_string())));
+        assert_eq!(parse_choice(""), Some(Choice::Skip));
+        assert_eq!(parse_choice("q"), Some(Choice::Quit));
+        assert_eq!(parse_choice("c"), None);
+        assert_eq!(parse_choice("maybe"), None);
+    }
+
+    #[test]
+    fn test_ask_applies_pending_category() {
+        let mut input = io::Cursor::new("x\nc crypto\ns\n");
+        let answer = ask(&mut input).unwrap();
+        assert_eq!(answer, Answer::Label(Label::Spam, Some("crypto".to_string())));
+
+        let mut input = io::Cursor::new("");
+        assert_eq!(ask(&mut input).unwrap(), Answer::Quit);
+    }
+}
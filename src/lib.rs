@@ -0,0 +1,32 @@
+//! Spam filter for Moltbook - the social network for AI agents.
+//!
+//! The crate has three parts. The first two can be used independently, and
+//! the third builds on the filter:
+//!
+//! - [`filter`] scores posts with configurable rules ([`SpamFilter`])
+//! - [`moltbook`] talks to the Moltbook API ([`MoltbookClient`])
+//! - [`corpus`] reads labeled posts and measures the filter against them
+//!
+//! ```
+//! use moltbook_filter::SpamFilter;
+//!
+//! let filter = SpamFilter::new();
+//! let analysis = filter.analyze("🦞 Just minted 1000 CLAW tokens!", "Buy now!", None);
+//! assert!(analysis.is_spam);
+//! ```
+//!
+//! The `cli` feature (on by default) builds the `moltbook_filter` binary.
+//! Library users can disable it to drop `clap` and `colored`:
+//!
+//! ```toml
+//! moltbook_filter = { version = "0.1", default-features = false }
+//! ```
+
+pub mod corpus;
+pub mod filter;
+mod jsonl;
+pub mod moltbook;
+
+pub use filter::{SpamAnalysis, SpamFilter};
+pub use jsonl::DataFileError;
+pub use moltbook::{Comment, MoltbookClient, Post, Profile};
@@ -1,21 +1,188 @@
-use clap::{Parser, Subcommand};
+use chrono::{DateTime, NaiveDate, Utc};
+use clap::{Args, Parser, Subcommand};
 use colored::*;
+use futures_util::TryStreamExt;
 use std::io::{self, Read};
+use std::path::{Path, PathBuf};
 
-mod filter;
-mod moltbook;
+use moltbook_filter::corpus::{
+    self, AuthorRecord, AuthorStore, Calibration, EvalReport, Label, Mark, Misclassified, Sample, Vote, VoteHistory,
+    VoteRecord,
+};
+use moltbook_filter::filter::{
+    self, BayesModel, ChangeKind, ConfigError, DomainList, DuplicateDetector, FloodDetector, InjectionRisk, PostView,
+    Scope, SpamAnalysis, SpamFilter, TextChange, Treatment,
+};
+use moltbook_filter::moltbook::{
+    self, Comment, FeedQuery, FeedSource, MoltbookClient, MoltbookError, Post, ProfileCache,
+};
+use serde::Serialize;
 
-use filter::SpamFilter;
-use moltbook::MoltbookClient;
+mod label;
+mod output;
+
+use output::Format;
 
 #[derive(Parser)]
 #[command(name = "moltbook-filter")]
 #[command(about = "Spam filter for Moltbook - filters noise, surfaces quality", long_about = None)]
 struct Cli {
+    /// Moltbook API base URL (e.g. a staging instance or a local mock server)
+    #[arg(long, env = "MOLTBOOK_API_BASE", global = true, default_value = moltbook::DEFAULT_API_BASE)]
+    api_base: String,
+
+    /// Output format
+    #[arg(long, value_enum, global = true, default_value_t = Format::Human)]
+    format: Format,
+
     #[command(subcommand)]
     command: Commands,
 }
 
+/// Options controlling how the spam filter is built
+#[derive(Args)]
+struct FilterArgs {
+    /// TOML rule file to use instead of the built-in rules
+    #[arg(long, value_name = "FILE")]
+    rules: Option<PathBuf>,
+
+    /// Spam threshold overriding the rule file's (see `calibrate`)
+    #[arg(long, value_name = "SCORE")]
+    threshold: Option<u32>,
+
+    /// Naive Bayes model written by `train`, scored alongside the rules
+    #[arg(long, value_name = "FILE")]
+    model: Option<PathBuf>,
+
+    /// Vote history to learn from: adjusts authors you voted on and adds the
+    /// voted posts to --model [default: ~/.moltbook_filter/votes.jsonl]
+    #[arg(long, env = "MOLTBOOK_VOTES", value_name = "FILE")]
+    votes: Option<PathBuf>,
+
+    /// Domains to trust, one per line; posts linking only to them score higher
+    #[arg(long, value_name = "FILE")]
+    allow_domains: Option<PathBuf>,
+
+    /// Domains to block, one per line; posts linking to them are penalized
+    #[arg(long, value_name = "FILE")]
+    block_domains: Option<PathBuf>,
+
+    /// Author reputation store; `feed` adds the posts it scores
+    /// [default: ~/.moltbook_filter/authors.jsonl]
+    #[arg(long, env = "MOLTBOOK_AUTHORS", value_name = "FILE")]
+    authors: Option<PathBuf>,
+}
+
+impl FilterArgs {
+    fn votes_path(&self) -> Option<PathBuf> {
+        self.votes.clone().or_else(|| data_file("votes.jsonl"))
+    }
+
+    fn authors_path(&self) -> Option<PathBuf> {
+        self.authors.clone().or_else(|| data_file("authors.jsonl"))
+    }
+
+    fn load(&self) -> Result<SpamFilter, ConfigError> {
+        let mut filter = match &self.rules {
+            Some(path) => SpamFilter::from_config(path)?,
+            None => SpamFilter::new(),
+        };
+        let history = match self.votes_path() {
+            Some(path) => Some(VoteHistory::load(&path).map_err(|e| ConfigError {
+                rule: None,
+                line: e.line,
+                message: format!("{}: {}", path.display(), e.message),
+            })?),
+            None => None,
+        };
+        if let Some(path) = &self.model {
+            let mut model = BayesModel::load(path)?;
+            for (text, spam) in history.iter().flat_map(VoteHistory::samples).map(|s| s.training_text()) {
+                model.learn(&text, spam);
+            }
+            filter = filter.with_model(model);
+        }
+        if let Some(history) = &history {
+            filter = filter.with_votes(history.author_balance());
+        }
+        if let Some(path) = self.authors_path() {
+            let store = AuthorStore::load(&path).map_err(|e| ConfigError {
+                rule: None,
+                line: e.line,
+                message: format!("{}: {}", path.display(), e.message),
+            })?;
+            filter = filter.with_reputation(store.reputations(Utc::now()));
+        }
+        if self.allow_domains.is_some() || self.block_domains.is_some() {
+            let load = |path: &Option<PathBuf>| path.as_ref().map(DomainList::load).transpose();
+            let allow = load(&self.allow_domains)?.unwrap_or_default();
+            let block = load(&self.block_domains)?.unwrap_or_default();
+            filter = filter.with_domains(allow, block);
+        }
+        Ok(match self.threshold {
+            Some(threshold) => filter.with_threshold(threshold),
+            None => filter,
+        })
+    }
+}
+
+/// How `feed`, `view` and `comments` make text safe for agents
+#[derive(Args)]
+struct SanitizeArgs {
+    /// Make text safe for agents: redact injections (or fence them off as
+    /// untrusted with --sanitize=fence), strip invisible characters, defang links
+    #[arg(long, value_name = "HOW", num_args = 0..=1, require_equals = true, default_missing_value = "redact")]
+    sanitize: Option<Treatment>,
+}
+
+impl SanitizeArgs {
+    /// Replace a post's title and content with sanitized text, returning what
+    /// changed; `None` without `--sanitize`
+    fn post(&self, post: &mut Post, analysis: &mut SpamAnalysis) -> Option<Vec<TextChange>> {
+        let how = self.sanitize?;
+        let title = filter::sanitize(&post.title, Scope::Title, how);
+        let content = filter::sanitize(&post.content, Scope::Content, how);
+        filter::sanitize_analysis(analysis, Some(&title), &content);
+        post.title = title.text;
+        post.content = content.text;
+        Some(title.changes.into_iter().chain(content.changes).collect())
+    }
+
+    fn comment(&self, comment: &mut Comment, analysis: &mut SpamAnalysis) -> Option<Vec<TextChange>> {
+        let content = filter::sanitize(&comment.content, Scope::Content, self.sanitize?);
+        filter::sanitize_analysis(analysis, None, &content);
+        comment.content = content.text;
+        Some(content.changes)
+    }
+}
+
+/// Where the vote commands keep their history
+#[derive(Args)]
+struct VoteArgs {
+    /// Vote history file [default: ~/.moltbook_filter/votes.jsonl]
+    #[arg(long, env = "MOLTBOOK_VOTES", value_name = "FILE")]
+    votes: Option<PathBuf>,
+
+    /// Don't record this vote in the history
+    #[arg(long)]
+    no_record: bool,
+}
+
+impl VoteArgs {
+    fn path(&self) -> Option<PathBuf> {
+        if self.no_record {
+            return None;
+        }
+        self.votes.clone().or_else(|| data_file("votes.jsonl"))
+    }
+}
+
+/// A file in ~/.moltbook_filter
+fn data_file(name: &str) -> Option<PathBuf> {
+    let home = std::env::var_os("HOME")?;
+    Some(Path::new(&home).join(".moltbook_filter").join(name))
+}
+
 #[derive(Subcommand)]
 enum Commands {
     /// Fetch and filter the Moltbook feed
@@ -24,7 +191,7 @@ enum Commands {
         #[arg(short, long, env = "MOLTBOOK_API_KEY")]
         api_key: String,
 
-        /// Number of posts to fetch
+        /// Number of posts to fetch (follows pages as needed)
         #[arg(short, long, default_value = "25")]
         limit: u32,
 
@@ -32,13 +199,62 @@ enum Commands {
         #[arg(short, long, default_value = "new")]
         sort: String,
 
+        /// Timeline to read: global, personal, submolt:<name> or user:<name>
+        #[arg(long, default_value = "global")]
+        source: FeedSource,
+
+        /// Only posts newer than this: an RFC 3339 time, a date, or an age like 30m, 24h, 7d
+        #[arg(long, value_parser = parse_since)]
+        since: Option<DateTime<Utc>>,
+
         /// Show spam posts too (for debugging)
         #[arg(long)]
         show_spam: bool,
 
-        /// Minimum quality score to show (0-100)
-        #[arg(long, default_value = "30")]
-        min_score: u32,
+        /// Minimum quality score to show (0-100) [default: the spam threshold]
+        #[arg(long)]
+        min_score: Option<u32>,
+
+        /// Remember post fingerprints in this file, so template copies are
+        /// caught across runs and not only within one
+        #[arg(long, value_name = "FILE")]
+        dedup_window: Option<PathBuf>,
+
+        /// Days of fingerprints to keep in --dedup-window
+        #[arg(long, default_value = "7", value_name = "DAYS")]
+        dedup_days: u32,
+
+        /// Share of word pairs two posts must share to count as copies (0-1)
+        #[arg(long, default_value = "0.6", value_name = "SHARE")]
+        dedup_similarity: f64,
+
+        /// Remember when authors posted in this file, so floods spread over
+        /// several runs are caught
+        #[arg(long, value_name = "FILE")]
+        flood_history: Option<PathBuf>,
+
+        /// Days of posting history to keep in --flood-history
+        #[arg(long, default_value = "7", value_name = "DAYS")]
+        flood_days: u32,
+
+        /// Fetch each author's profile and score the account: brand-new
+        /// accounts, no karma after many posts, follow farming
+        #[arg(long)]
+        profiles: bool,
+
+        /// Where fetched profiles are cached [default: ~/.moltbook_filter/profiles.jsonl]
+        #[arg(long, value_name = "FILE")]
+        profile_cache: Option<PathBuf>,
+
+        /// Hours a cached profile stays fresh
+        #[arg(long, default_value = "24", value_name = "HOURS")]
+        profile_ttl: u32,
+
+        #[command(flatten)]
+        sanitize: SanitizeArgs,
+
+        #[command(flatten)]
+        filter: FilterArgs,
     },
     /// Analyze a single post for spam
     Analyze {
@@ -53,12 +269,104 @@ enum Commands {
         /// Author name
         #[arg(short, long)]
         author: Option<String>,
+
+        #[command(flatten)]
+        filter: FilterArgs,
     },
     /// Show spam detection rules
-    Rules,
+    Rules {
+        #[command(flatten)]
+        filter: FilterArgs,
+    },
+    /// Measure the filter against a labeled JSONL corpus
+    Eval {
+        /// Corpus file, one {"title", "content", "author", "label", "category"} object per line
+        #[arg(long, value_name = "FILE")]
+        corpus: PathBuf,
+
+        /// Number of worst false positives/negatives to list
+        #[arg(long, default_value = "5")]
+        top: usize,
+
+        #[command(flatten)]
+        filter: FilterArgs,
+    },
+    /// Sweep spam thresholds over a labeled corpus and suggest one
+    Calibrate {
+        /// Corpus file, in the same format as for `eval`
+        #[arg(long, value_name = "FILE")]
+        corpus: PathBuf,
+
+        /// Highest acceptable share of ham flagged as spam
+        #[arg(long, default_value = "0.05")]
+        target_fpr: f64,
+
+        /// Print every n-th threshold in the table
+        #[arg(long, default_value = "5", value_parser = clap::value_parser!(u32).range(1..))]
+        step: u32,
+
+        #[command(flatten)]
+        filter: FilterArgs,
+    },
+    /// Train a naive Bayes model on a labeled corpus
+    Train {
+        /// Corpus file, in the same format as for `eval`
+        #[arg(long, value_name = "FILE")]
+        corpus: PathBuf,
+
+        /// Where to write the model (load it with --model)
+        #[arg(short, long, value_name = "FILE")]
+        output: PathBuf,
+
+        /// Largest score delta the model may contribute
+        #[arg(long, default_value = "30", value_parser = clap::value_parser!(i32).range(1..))]
+        max_weight: i32,
+
+        /// Ignore tokens found in fewer posts than this
+        #[arg(long, default_value = "2")]
+        min_count: u32,
+
+        /// Number of most telling tokens to list for each class
+        #[arg(long, default_value = "10")]
+        top: usize,
+    },
+    /// Label posts from the live feed into a corpus, skipping ones already labeled
+    Label {
+        /// Moltbook API key
+        #[arg(short, long, env = "MOLTBOOK_API_KEY")]
+        api_key: String,
+
+        /// Corpus file to append to (created if missing)
+        #[arg(long, value_name = "FILE")]
+        corpus: PathBuf,
+
+        /// Number of posts to fetch (follows pages as needed)
+        #[arg(short, long, default_value = "25")]
+        limit: u32,
+
+        /// Sort order (hot, new, top)
+        #[arg(short, long, default_value = "new")]
+        sort: String,
+
+        /// Timeline to read: global, personal, submolt:<name> or user:<name>
+        #[arg(long, default_value = "global")]
+        source: FeedSource,
+
+        #[command(flatten)]
+        filter: FilterArgs,
+    },
 
     // === INTERACTION COMMANDS ===
 
+    /// Manage the author reputation store
+    Authors {
+        #[command(subcommand)]
+        action: AuthorsAction,
+
+        /// Author reputation store [default: ~/.moltbook_filter/authors.jsonl]
+        #[arg(long, env = "MOLTBOOK_AUTHORS", value_name = "FILE", global = true)]
+        authors: Option<PathBuf>,
+    },
     /// Create a new post on Moltbook
     Post {
         /// Moltbook API key
@@ -86,6 +394,9 @@ enum Commands {
         /// Post ID to upvote
         #[arg(short, long)]
         post_id: String,
+
+        #[command(flatten)]
+        history: VoteArgs,
     },
     /// Downvote a post
     Downvote {
@@ -96,6 +407,9 @@ enum Commands {
         /// Post ID to downvote
         #[arg(short, long)]
         post_id: String,
+
+        #[command(flatten)]
+        history: VoteArgs,
     },
     /// Remove vote from a post
     Unvote {
@@ -106,6 +420,9 @@ enum Commands {
         /// Post ID to unvote
         #[arg(short, long)]
         post_id: String,
+
+        #[command(flatten)]
+        history: VoteArgs,
     },
     /// Add a comment to a post
     Comment {
@@ -130,6 +447,20 @@ enum Commands {
         /// Post ID to view comments for
         #[arg(short, long)]
         post_id: String,
+
+        /// Show spam comments too
+        #[arg(long)]
+        show_spam: bool,
+
+        /// Minimum quality score to show [default: the spam threshold]
+        #[arg(long)]
+        min_score: Option<u32>,
+
+        #[command(flatten)]
+        sanitize: SanitizeArgs,
+
+        #[command(flatten)]
+        filter: FilterArgs,
     },
     /// View your profile stats
     Profile {
@@ -150,35 +481,402 @@ enum Commands {
         /// Post ID to view
         #[arg(short, long)]
         post_id: String,
+
+        #[command(flatten)]
+        sanitize: SanitizeArgs,
+
+        #[command(flatten)]
+        filter: FilterArgs,
+    },
+}
+
+#[derive(Subcommand)]
+enum AuthorsAction {
+    /// List known authors with their record and reputation
+    List {
+        /// Only authors you allowed or denied
+        #[arg(long)]
+        marked: bool,
+
+        /// Show at most this many, most active first
+        #[arg(short, long)]
+        limit: Option<usize>,
+    },
+    /// Show everything recorded about an author
+    Show {
+        name: String,
+    },
+    /// Trust an author: their posts gain 20 points
+    Allow {
+        name: String,
+
+        /// Why, for your own reference
+        #[arg(long)]
+        note: Option<String>,
+    },
+    /// Block an author: their posts lose 40 points
+    Deny {
+        name: String,
+
+        /// Why, for your own reference
+        #[arg(long)]
+        note: Option<String>,
+    },
+    /// Remove your mark and go by the author's record again
+    Unmark {
+        name: String,
     },
 }
 
+/// An author's record with the reputation it earns, as emitted by `authors`
+#[derive(Serialize)]
+struct AuthorSummary {
+    name: String,
+    posts: u32,
+    spam: u32,
+    average_score: Option<f64>,
+    spam_ratio: Option<f64>,
+    mark: Option<Mark>,
+    note: Option<String>,
+    reputation: i32,
+    last_seen: Option<DateTime<Utc>>,
+}
+
+impl AuthorSummary {
+    fn new(record: &AuthorRecord) -> Self {
+        Self {
+            name: record.name.clone(),
+            posts: record.posts,
+            spam: record.spam,
+            average_score: record.average_score().map(|a| (a * 10.0).round() / 10.0),
+            spam_ratio: record.spam_ratio().map(|r| (r * 1000.0).round() / 1000.0),
+            mark: record.mark,
+            note: record.note.clone(),
+            reputation: record.reputation(Utc::now()),
+            last_seen: record.last_seen,
+        }
+    }
+
+    /// One-line description for the human output
+    fn line(&self) -> String {
+        let reputation = match self.reputation {
+            r if r > 0 => format!("{:+4}", r).green(),
+            r if r < 0 => format!("{:+4}", r).red(),
+            _ => format!("{:+4}", 0).dimmed(),
+        };
+        let record = match (self.average_score, self.spam_ratio) {
+            (Some(average), Some(ratio)) => {
+                format!("{} posts, avg score {:.0}, {:.0}% spam", self.posts, average, ratio * 100.0)
+            }
+            _ => "no posts seen".to_string(),
+        };
+        let mark = match self.mark {
+            Some(Mark::Allow) => " [allowed]".green(),
+            Some(Mark::Deny) => " [denied]".red(),
+            None => "".normal(),
+        };
+        format!("{} {} {}{}", reputation, self.name.cyan(), record.dimmed(), mark)
+    }
+}
+
+/// A post with its spam analysis, as emitted by `feed` and `view`
+#[derive(Serialize)]
+struct AnalyzedPost {
+    #[serde(flatten)]
+    post: Post,
+    analysis: SpamAnalysis,
+    /// What `--sanitize` changed in the title and content
+    #[serde(skip_serializing_if = "Option::is_none")]
+    sanitized: Option<Vec<TextChange>>,
+}
+
+/// A comment with its spam analysis, as emitted by `comments`
+#[derive(Serialize)]
+struct AnalyzedComment {
+    #[serde(flatten)]
+    comment: Comment,
+    analysis: SpamAnalysis,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    sanitized: Option<Vec<TextChange>>,
+}
+
+/// List what `--sanitize` changed, under a post or comment
+fn print_changes(changes: &[TextChange]) {
+    if changes.is_empty() {
+        return;
+    }
+    println!("    🧼 Sanitized:");
+    for change in changes {
+        let what = match change.kind {
+            ChangeKind::Redacted => "redacted",
+            ChangeKind::Fenced => "fenced",
+            ChangeKind::StrippedInvisible => "stripped",
+            ChangeKind::DefangedLink => "defanged",
+        };
+        println!("      {} {}", what.yellow(), change.note.dimmed());
+    }
+}
+
+/// Process exit code for each kind of API failure (1 is config errors, 2 is usage)
+fn exit_code(e: &MoltbookError) -> i32 {
+    match e {
+        MoltbookError::Transport(_) => 3,
+        MoltbookError::Unauthorized => 4,
+        MoltbookError::NotFound => 5,
+        MoltbookError::RateLimited { .. } => 6,
+        MoltbookError::Http { .. } => 7,
+        MoltbookError::Decode(_) => 8,
+        MoltbookError::Api { .. } => 9,
+        MoltbookError::Timeout(_) => 10,
+        MoltbookError::RetriesExhausted { last, .. } => exit_code(last),
+    }
+}
+
+/// Short machine-readable name of an API error, used in JSON error output
+fn error_kind(e: &MoltbookError) -> &'static str {
+    match e {
+        MoltbookError::Transport(_) => "transport",
+        MoltbookError::Unauthorized => "unauthorized",
+        MoltbookError::NotFound => "not_found",
+        MoltbookError::RateLimited { .. } => "rate_limited",
+        MoltbookError::Http { .. } => "http",
+        MoltbookError::Decode(_) => "decode",
+        MoltbookError::Api { .. } => "api",
+        MoltbookError::Timeout(_) => "timeout",
+        MoltbookError::RetriesExhausted { last, .. } => error_kind(last),
+    }
+}
+
+/// Report an API error and exit with its exit code
+fn fail(e: MoltbookError) -> ! {
+    let code = exit_code(&e);
+    output::error(error_kind(&e), &e, code);
+    std::process::exit(code);
+}
+
+/// Parse `--since`: an RFC 3339 timestamp, a YYYY-MM-DD date, or an age such as `24h`
+fn parse_since(value: &str) -> Result<DateTime<Utc>, String> {
+    if let Ok(time) = DateTime::parse_from_rfc3339(value) {
+        return Ok(time.with_timezone(&Utc));
+    }
+    if let Ok(date) = NaiveDate::parse_from_str(value, "%Y-%m-%d") {
+        return Ok(date.and_hms_opt(0, 0, 0).unwrap().and_utc());
+    }
+
+    let (amount, unit) = value.split_at(value.len().saturating_sub(1));
+    let amount: i64 = amount
+        .parse()
+        .map_err(|_| format!("expected a time, a date or an age like 24h, got `{}`", value))?;
+    let age = match unit {
+        "m" => chrono::Duration::minutes(amount),
+        "h" => chrono::Duration::hours(amount),
+        "d" => chrono::Duration::days(amount),
+        "w" => chrono::Duration::weeks(amount),
+        _ => return Err(format!("unknown unit `{}`, use m, h, d or w", unit)),
+    };
+    Ok(Utc::now() - age)
+}
+
+/// Build an API client or exit if the HTTP client can't be created
+fn connect(api_base: &str, api_key: String) -> MoltbookClient {
+    MoltbookClient::builder()
+        .api_key(api_key)
+        .base_url(api_base)
+        .build()
+        .unwrap_or_else(|e| fail(e))
+}
+
+/// Load the spam filter or exit with the config error
+fn load_filter(args: &FilterArgs) -> SpamFilter {
+    args.load().unwrap_or_else(|e| {
+        output::error("config", &e, 1);
+        std::process::exit(1);
+    })
+}
+
+/// Load a labeled corpus or exit with the parse error
+fn load_corpus(path: &Path) -> Vec<Sample> {
+    corpus::load(path).unwrap_or_else(|e| {
+        output::error("corpus", format!("{}: {}", path.display(), e), 1);
+        std::process::exit(1);
+    })
+}
+
+/// Save a vote by post id; `feed --votes` adds the post once it sees it.
+/// Failing to record doesn't fail the vote.
+fn record_vote(args: &VoteArgs, post_id: &str, action: Vote) {
+    let Some(path) = args.path() else { return };
+    if let Err(e) = VoteRecord::for_id(post_id, action).save(&path) {
+        output::warn("votes", format!("vote not recorded in {}: {}", path.display(), e));
+    }
+}
+
+/// Print the worst mistakes of one kind in an eval report
+fn print_misclassified(heading: &str, items: &[Misclassified]) {
+    if items.is_empty() {
+        return;
+    }
+    println!("\n{}", heading.bold());
+    for item in items {
+        let id = item.id.as_deref().map(|id| format!(" [id:{}]", id)).unwrap_or_default();
+        let category = item.category.as_deref().map(|c| format!(" ({})", c)).unwrap_or_default();
+        println!("  [{}] {}{}{}", item.score, item.title, category.dimmed(), id.dimmed());
+        if !item.flags.is_empty() {
+            println!("      Flags: {}", item.flags.join(", ").dimmed());
+        }
+        if !item.positive_signals.is_empty() {
+            println!("      Signals: {}", item.positive_signals.join(", ").dimmed());
+        }
+    }
+}
+
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
     let cli = Cli::parse();
-    let filter = SpamFilter::new();
+    let api_base = cli.api_base;
+    output::set_format(cli.format);
 
     match cli.command {
         Commands::Feed {
             api_key,
             limit,
             sort,
+            source,
+            since,
             show_spam,
             min_score,
+            dedup_window,
+            dedup_days,
+            dedup_similarity,
+            flood_history,
+            flood_days,
+            profiles,
+            profile_cache,
+            profile_ttl,
+            sanitize,
+            filter,
         } => {
-            let client = MoltbookClient::new(api_key);
-            println!("{}", "🦞 Fetching Moltbook feed...".cyan());
+            let authors_path = filter.authors_path();
+            let votes_path = filter.votes_path();
+            let filter = load_filter(&filter);
+            // Already read once by load_filter, so this only fails on a race
+            let mut authors = authors_path.as_ref().and_then(|path| AuthorStore::load(path).ok());
+            let min_score = min_score.unwrap_or(filter.threshold());
+            let detector = match &dedup_window {
+                Some(path) => DuplicateDetector::load(path, chrono::Duration::days(dedup_days.into()))
+                    .map_err(|e| format!("{}: {}", path.display(), e)),
+                None => Ok(DuplicateDetector::new()),
+            };
+            let mut detector = match detector {
+                Ok(detector) => detector.with_min_similarity(dedup_similarity),
+                Err(e) => {
+                    output::error("config", format!("Invalid dedup window: {}", e), 1);
+                    std::process::exit(1);
+                }
+            };
+            let mut flood = match &flood_history {
+                Some(path) => FloodDetector::load(path, chrono::Duration::days(flood_days.into()))
+                    .map_err(|e| format!("{}: {}", path.display(), e)),
+                None => Ok(FloodDetector::new()),
+            }
+            .unwrap_or_else(|e| {
+                output::error("config", format!("Invalid flood history: {}", e), 1);
+                std::process::exit(1);
+            });
+            let client = connect(&api_base, api_key);
+            output::status(format!("🦞 Fetching Moltbook feed ({})...", source).cyan());
+
+            let mut query = FeedQuery::new(sort).source(source).limit(limit as usize);
+            if let Some(since) = since {
+                query = query.since(since);
+            }
 
-            match client.get_feed(&sort, limit).await {
+            match client.feed_stream(query).try_collect::<Vec<_>>().await {
                 Ok(posts) => {
                     let mut quality_count = 0;
                     let mut spam_count = 0;
+                    let mut entries = Vec::new();
 
-                    println!("\n{}\n", "━".repeat(60).dimmed());
+                    output::status(format!("\n{}\n", "━".repeat(60).dimmed()));
+
+                    // Votes cast by id get the post now that it's here
+                    if let Some(path) = &votes_path {
+                        let mut history = VoteHistory::load(path).unwrap_or_default();
+                        let mut changed = false;
+                        for post in &posts {
+                            changed |= history.fill(post);
+                        }
+                        if changed {
+                            if let Err(e) = history.save(path) {
+                                output::warn("votes", format!("votes not updated in {}: {}", path.display(), e));
+                            }
+                        }
+                    }
+
+                    // Rates need every post of the batch, so note them all before scoring any
+                    for post in &posts {
+                        if let (Some(author), Some(at)) = (&post.author, post.created()) {
+                            flood.observe(author, &post.id, &post.title, at);
+                        }
+                    }
+
+                    let profile_ttl = chrono::Duration::hours(profile_ttl.into());
+                    let profile_cache = profile_cache.or_else(|| data_file("profiles.jsonl"));
+                    let mut cache = match (&profile_cache, profiles) {
+                        (Some(path), true) => ProfileCache::load(path),
+                        _ => ProfileCache::new(),
+                    };
+                    if profiles {
+                        let mut missing: Vec<&str> = posts
+                            .iter()
+                            .filter_map(|p| p.author.as_deref())
+                            .filter(|a| cache.get(a, profile_ttl).is_none())
+                            .collect();
+                        missing.sort_unstable_by_key(|a| a.to_lowercase());
+                        missing.dedup_by_key(|a| a.to_lowercase());
+                        for author in missing {
+                            match client.get_profile(author).await {
+                                Ok(profile) => cache.insert(profile),
+                                Err(e) => output::warn("profiles", format!("no profile for {}: {}", author, e)),
+                            }
+                        }
+                        if let Some(path) = &profile_cache {
+                            if let Err(e) = cache.save(path) {
+                                output::warn("profiles", format!("profiles not cached in {}: {}", path.display(), e));
+                            }
+                        }
+                    }
+
+                    for mut post in posts {
+                        let view = PostView::new(&post.title, &post.content, post.author.as_deref());
+                        let mut analysis = filter.analyze_view(&view);
+                        if let Some(hit) = detector.check(&post.id, &view) {
+                            filter.add_hit(&mut analysis, hit);
+                        }
+                        if let Some(hit) = post.author.as_deref().and_then(|a| flood.check(a)) {
+                            filter.add_hit(&mut analysis, hit);
+                        }
+                        if let (Some(store), Some(author)) = (&mut authors, &post.author) {
+                            // Record the post on its own merits, not the reputation it was scored with
+                            let own = filter.excluding(&analysis, "author_reputation");
+                            store.record(author, &post.id, own.score, own.is_spam);
+                        }
+                        // Account signals say nothing about the post, so they stay out of its record
+                        if let Some(profile) = post.author.as_deref().and_then(|a| cache.get(a, profile_ttl)) {
+                            for hit in filter::account_hits(profile, Utc::now()) {
+                                filter.add_hit(&mut analysis, hit);
+                            }
+                        }
+
+                        let sanitized = sanitize.post(&mut post, &mut analysis);
+
+                        if !output::is_human() {
+                            if analysis.score >= min_score || show_spam {
+                                entries.push(AnalyzedPost { post, analysis, sanitized });
+                            }
+                            continue;
+                        }
 
-                    for post in posts {
-                        let analysis = filter.analyze(&post.title, &post.content, post.author.as_deref());
-                        
                         if analysis.score >= min_score || show_spam {
                             let score_color = if analysis.score >= 70 {
                                 format!("{}", analysis.score).green()
@@ -204,6 +902,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
                             if !analysis.flags.is_empty() {
                                 println!("    Flags: {}", analysis.flags.join(", ").dimmed());
                             }
+                            print_changes(sanitized.as_deref().unwrap_or_default());
                             
                             if analysis.score >= min_score {
                                 quality_count += 1;
@@ -216,22 +915,67 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
                         }
                     }
 
+                    if let Some(path) = &dedup_window {
+                        if let Err(e) = detector.save(path) {
+                            output::warn("dedup", format!("dedup window not saved to {}: {}", path.display(), e));
+                        }
+                    }
+                    if let Some(path) = &flood_history {
+                        if let Err(e) = flood.save(path) {
+                            output::warn("flood", format!("flood history not saved to {}: {}", path.display(), e));
+                        }
+                    }
+                    if let (Some(store), Some(path)) = (&authors, &authors_path) {
+                        if let Err(e) = store.save(path) {
+                            output::warn("authors", format!("authors not saved to {}: {}", path.display(), e));
+                        }
+                    }
+
+                    if !output::is_human() {
+                        output::emit_list(&entries);
+                        return Ok(());
+                    }
+
                     println!("{}", "━".repeat(60).dimmed());
                     println!(
                         "📊 {} quality posts, {} filtered as spam",
                         quality_count.to_string().green(),
                         spam_count.to_string().red()
                     );
+                    let clusters = detector.clusters();
+                    if !clusters.is_empty() {
+                        println!("🧬 {} template clusters:", clusters.len());
+                        for cluster in &clusters {
+                            println!("    {} × {}", cluster.size().to_string().red(), cluster.title);
+                        }
+                    }
+                    let floods = flood.floods();
+                    if !floods.is_empty() {
+                        println!("🌊 {} flooding authors:", floods.len());
+                        for activity in &floods {
+                            let reasons: Vec<String> = activity.reasons().into_iter().map(|(_, r)| r).collect();
+                            println!("    {} {}", activity.author.red(), reasons.join(", ").dimmed());
+                        }
+                    }
                 }
-                Err(e) => {
-                    eprintln!("{} {}", "Error:".red(), e);
-                }
+                Err(e) => fail(e),
             }
         }
 
-        Commands::Analyze { title, content, author } => {
+        Commands::Analyze { title, content, author, filter } => {
+            let filter = load_filter(&filter);
             let analysis = filter.analyze(&title, &content, author.as_deref());
-            
+
+            if !output::is_human() {
+                output::emit(&serde_json::json!({
+                    "title": title,
+                    "content": content,
+                    "author": author,
+                    "analysis": analysis,
+                }));
+                return Ok(());
+            }
+
             println!("\n{}", "📋 Spam Analysis".bold());
             println!("{}", "━".repeat(40));
             println!("Title: {}", title.cyan());
@@ -259,36 +1003,346 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
                     println!("  ✓ {}", signal.green());
                 }
             }
+
+            if !analysis.links.is_empty() {
+                println!("\nLinks:");
+                for link in &analysis.links {
+                    println!("  → {} {}", link.host.cyan(), link.url.dimmed());
+                }
+            }
+
+            if !analysis.injection.is_empty() {
+                let risk = match analysis.injection_risk {
+                    InjectionRisk::High => analysis.injection_risk.to_string().red(),
+                    InjectionRisk::Medium => analysis.injection_risk.to_string().yellow(),
+                    _ => analysis.injection_risk.to_string().normal(),
+                };
+                println!("\nPrompt injection ({} risk):", risk);
+                for finding in &analysis.injection {
+                    let via = finding.encoding.as_deref().map(|e| format!(" ({})", e)).unwrap_or_default();
+                    println!("  ⚠ {}{}: {}", finding.kind.describe(), via, finding.text.dimmed());
+                }
+            }
         }
 
-        Commands::Rules => {
+        Commands::Rules { filter } => {
+            let filter = load_filter(&filter);
+
+            if !output::is_human() {
+                let rules: Vec<_> = filter
+                    .rules()
+                    .map(|rule| serde_json::json!({
+                        "name": rule.name(),
+                        "description": rule.description(),
+                        "weights": rule.weights(),
+                    }))
+                    .collect();
+                output::emit(&serde_json::json!({ "threshold": filter.threshold(), "rules": rules }));
+                return Ok(());
+            }
+
+            let (negative, positive): (Vec<_>, Vec<_>) = filter
+                .rules()
+                .partition(|rule| rule.weights().iter().all(|w| *w < 0));
+
             println!("\n{}", "🔍 Spam Detection Rules".bold());
             println!("{}\n", "━".repeat(40));
-            
+
             println!("{}", "❌ Negative Patterns (reduce score):".red());
-            println!("  • CLAW/token minting spam (-40)");
-            println!("  • Crypto shilling, token launches (-35)");
-            println!("  • Prompt injection attempts (-50)");
-            println!("  • Empty/minimal content (-30)");
-            println!("  • Generic hourly check-ins (-25)");
-            println!("  • Excessive emojis/buzzwords (-20)");
-            println!("  • VC/promotional content (-30)");
-            println!("  • Religious cult recruitment (-35)");
-            println!("  • ALL CAPS shouting (-15)");
-            
+            for rule in negative {
+                println!("  • {}", describe_rule(rule));
+            }
+
             println!("\n{}", "✓ Positive Signals (increase score):".green());
-            println!("  • Technical content (+20)");
-            println!("  • Code snippets (+15)");
-            println!("  • Questions that invite discussion (+10)");
-            println!("  • Reasonable length with substance (+10)");
-            println!("  • References to other posts/agents (+5)");
-            println!("  • Known quality authors (+15)");
+            for rule in positive {
+                println!("  • {}", describe_rule(rule));
+            }
+
+            println!("\nSpam threshold: {}", filter.threshold());
+        }
+
+        Commands::Eval { corpus, top, filter } => {
+            let filter = load_filter(&filter);
+            let samples = load_corpus(&corpus);
+            let report = EvalReport::run(&filter, &samples, top);
+
+            if !output::is_human() {
+                output::emit(&report);
+                return Ok(());
+            }
+
+            let c = &report.confusion;
+            println!("\n{}", "📏 Filter Evaluation".bold());
+            println!("{}", "━".repeat(40));
+            println!("Samples: {}, threshold: {}", report.samples, report.threshold);
+            println!(
+                "Precision: {:.3}  Recall: {:.3}  F1: {:.3}",
+                report.precision, report.recall, report.f1
+            );
+
+            println!("\n{}", "Confusion matrix:".bold());
+            println!("  {:<14}{:>16}{:>16}", "", "predicted spam", "predicted ham");
+            println!("  {:<14}{:>16}{:>16}", "actual spam", c.true_positives, c.false_negatives);
+            println!("  {:<14}{:>16}{:>16}", "actual ham", c.false_positives, c.true_negatives);
+
+            println!("\n{}", "Rule hit rates:".bold());
+            println!("  {:<20}{:>8}{:>8}", "rule", "spam", "ham");
+            for flag in &report.flags {
+                println!(
+                    "  {:<20}{:>7.1}%{:>7.1}%",
+                    flag.rule,
+                    flag.spam_rate * 100.0,
+                    flag.ham_rate * 100.0
+                );
+            }
+
+            print_misclassified("Worst false positives (ham scored as spam):", &report.worst_false_positives);
+            print_misclassified("Worst false negatives (spam that got through):", &report.worst_false_negatives);
+        }
+
+        Commands::Calibrate { corpus, target_fpr, step, filter } => {
+            let filter = load_filter(&filter);
+            let samples = load_corpus(&corpus);
+            let calibration = Calibration::run(&filter, &samples, target_fpr);
+
+            if !output::is_human() {
+                output::emit(&calibration);
+                return Ok(());
+            }
+
+            println!("\n{}", "🎯 Threshold Calibration".bold());
+            println!("{}", "━".repeat(52));
+            println!("Samples: {}, ROC AUC: {:.3}\n", calibration.samples, calibration.roc_auc);
+            println!(
+                "  {:>9}{:>11}{:>9}{:>8}{:>7}",
+                "threshold", "precision", "recall", "FPR", "F1"
+            );
+            for point in &calibration.points {
+                let current = point.threshold == filter.threshold();
+                let suggested = Some(point.threshold) == calibration.suggested;
+                if point.threshold % step != 0 && !current && !suggested {
+                    continue;
+                }
+                let row = format!(
+                    "  {:>9}{:>11.3}{:>9.3}{:>8.3}{:>7.3}",
+                    point.threshold, point.precision, point.recall, point.false_positive_rate, point.f1
+                );
+                match (current, suggested) {
+                    (_, true) => println!("{}  {}", row.green(), "← suggested".green()),
+                    (true, false) => println!("{}  {}", row, "← current".dimmed()),
+                    _ => println!("{}", row),
+                }
+            }
+
+            match calibration.suggested {
+                Some(threshold) => {
+                    let point = calibration.point(threshold).expect("suggested threshold is swept");
+                    println!(
+                        "\nSuggested threshold: {} (catches {:.1}% of spam, flags {:.1}% of ham)",
+                        threshold.to_string().green().bold(),
+                        point.recall * 100.0,
+                        point.false_positive_rate * 100.0
+                    );
+                    println!("Set `threshold = {}` in your rule file or pass --threshold {}", threshold, threshold);
+                }
+                None => println!(
+                    "\n{}",
+                    format!("No threshold catches spam while keeping the false-positive rate within {}", target_fpr).yellow()
+                ),
+            }
+        }
+
+        Commands::Train { corpus, output: model_path, max_weight, min_count, top } => {
+            let samples = load_corpus(&corpus);
+            let mut model = BayesModel::train(samples.iter().map(Sample::training_text));
+            model.max_weight = max_weight;
+            model.prune(min_count);
+            if let Err(e) = model.save(&model_path) {
+                output::error("model", format!("failed to write {}: {}", model_path.display(), e), 1);
+                std::process::exit(1);
+            }
+
+            let telling = |spam: bool| -> Vec<_> {
+                model
+                    .top_tokens(spam, top)
+                    .into_iter()
+                    .map(|(token, weight)| serde_json::json!({ "token": token, "weight": weight }))
+                    .collect()
+            };
+            if !output::is_human() {
+                output::emit(&serde_json::json!({
+                    "model": model_path,
+                    "spam_docs": model.spam_docs,
+                    "ham_docs": model.ham_docs,
+                    "vocabulary": model.tokens.len(),
+                    "spam_tokens": telling(true),
+                    "ham_tokens": telling(false),
+                }));
+                return Ok(());
+            }
+
+            println!("\n{}", "🧠 Naive Bayes Model".bold());
+            println!("{}", "━".repeat(40));
+            println!(
+                "Trained on {} spam and {} ham posts, {} tokens kept",
+                model.spam_docs,
+                model.ham_docs,
+                model.tokens.len()
+            );
+            let list = |spam: bool| {
+                model
+                    .top_tokens(spam, top)
+                    .into_iter()
+                    .map(|(token, weight)| format!("{} ({:+.2})", token, weight))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            };
+            println!("\n{} {}", "Spam tokens:".red(), list(true));
+            println!("{} {}", "Ham tokens:".green(), list(false));
+            println!("\nSaved to {}; score with it using --model {}", model_path.display(), model_path.display());
+        }
+
+        Commands::Authors { action, authors } => {
+            let Some(path) = authors.or_else(|| data_file("authors.jsonl")) else {
+                output::error("authors", "no author store: pass --authors or set HOME", 2);
+                std::process::exit(2);
+            };
+            let mut store = AuthorStore::load(&path).unwrap_or_else(|e| {
+                output::error("authors", format!("{}: {}", path.display(), e), 1);
+                std::process::exit(1);
+            });
+
+            let (name, mark, note) = match action {
+                AuthorsAction::List { marked, limit } => {
+                    let mut records: Vec<&AuthorRecord> =
+                        store.records().into_iter().filter(|r| !marked || r.mark.is_some()).collect();
+                    records.sort_by_key(|r| std::cmp::Reverse(r.posts));
+                    records.truncate(limit.unwrap_or(usize::MAX));
+                    let summaries: Vec<AuthorSummary> = records.into_iter().map(AuthorSummary::new).collect();
+
+                    if !output::is_human() {
+                        output::emit_list(&summaries);
+                        return Ok(());
+                    }
+                    println!("\n{}", format!("👥 Authors ({})", summaries.len()).bold());
+                    println!("{}", "━".repeat(40));
+                    for summary in &summaries {
+                        println!("{}", summary.line());
+                    }
+                    return Ok(());
+                }
+                AuthorsAction::Show { name } => {
+                    let Some(record) = store.get(&name) else {
+                        output::error("not_found", format!("no record of author {}", name), 5);
+                        std::process::exit(5);
+                    };
+                    let summary = AuthorSummary::new(record);
+                    if !output::is_human() {
+                        output::emit(&summary);
+                        return Ok(());
+                    }
+                    println!("\n{}", summary.line());
+                    if let Some(note) = &summary.note {
+                        println!("    Note: {}", note);
+                    }
+                    if let Some(last_seen) = summary.last_seen {
+                        println!("    Last seen: {}", last_seen.format("%Y-%m-%d %H:%M UTC"));
+                    }
+                    return Ok(());
+                }
+                AuthorsAction::Allow { name, note } => (name, Some(Mark::Allow), note),
+                AuthorsAction::Deny { name, note } => (name, Some(Mark::Deny), note),
+                AuthorsAction::Unmark { name } => (name, None, None),
+            };
+
+            store.mark(&name, mark, note);
+            if let Err(e) = store.save(&path) {
+                output::error("authors", e, 1);
+                std::process::exit(1);
+            }
+            let summary = AuthorSummary::new(store.get(&name).expect("just marked"));
+            if !output::is_human() {
+                output::emit(&summary);
+                return Ok(());
+            }
+            let verb = match mark {
+                Some(Mark::Allow) => "Allowed",
+                Some(Mark::Deny) => "Denied",
+                None => "Unmarked",
+            };
+            println!("{} {}", format!("✓ {}", verb).green(), summary.line());
+        }
+
+        Commands::Label { api_key, corpus, limit, sort, source, filter } => {
+            let filter = load_filter(&filter);
+            let mut seen = if corpus.exists() {
+                corpus::labeled_ids(&load_corpus(&corpus))
+            } else {
+                Default::default()
+            };
+            let client = connect(&api_base, api_key);
+            output::status(format!("🦞 Fetching Moltbook feed ({})...", source).cyan());
+
+            let query = FeedQuery::new(sort).source(source).limit(limit as usize);
+            let posts = client.feed_stream(query).try_collect::<Vec<_>>().await.unwrap_or_else(|e| fail(e));
+
+            let mut session = label::Session::default();
+            let (posts, labeled): (Vec<_>, Vec<_>) = posts.into_iter().partition(|post| !seen.contains(&post.id));
+            session.already_labeled = labeled.len();
+            output::status(format!(
+                "{} posts to label, {} already in {}",
+                posts.len(),
+                session.already_labeled,
+                corpus.display()
+            ));
+
+            let mut stdin = io::stdin().lock();
+            for (i, post) in posts.iter().enumerate() {
+                // The feed can repeat a post across pages
+                if seen.contains(&post.id) {
+                    session.already_labeled += 1;
+                    continue;
+                }
+                let analysis = filter.analyze(&post.title, &post.content, post.author.as_deref());
+                label::show(post, &analysis, i + 1, posts.len());
+
+                match label::ask(&mut stdin)? {
+                    label::Answer::Label(class, category) => {
+                        let sample = Sample::from_post(post, class, category);
+                        if let Err(e) = corpus::append(&corpus, &[sample]) {
+                            output::error("corpus", e, 1);
+                            std::process::exit(1);
+                        }
+                        seen.insert(post.id.clone());
+                        match class {
+                            Label::Spam => session.spam += 1,
+                            Label::Ham => session.ham += 1,
+                        }
+                    }
+                    label::Answer::Skip => session.skipped += 1,
+                    label::Answer::Quit => break,
+                }
+            }
+
+            if !output::is_human() {
+                output::emit(&session);
+                return Ok(());
+            }
+
+            println!("{}", "━".repeat(60).dimmed());
+            println!(
+                "🏷  Labeled {} spam and {} ham, skipped {} (saved to {})",
+                session.spam.to_string().red(),
+                session.ham.to_string().green(),
+                session.skipped,
+                corpus.display()
+            );
         }
 
         // === INTERACTION COMMANDS ===
 
         Commands::Post { api_key, title, content, submolt } => {
-            let client = MoltbookClient::new(api_key);
+            let client = connect(&api_base, api_key);
             
             // Support reading content from stdin
             let actual_content = if content == "-" {
@@ -299,10 +1353,15 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
                 content
             };
 
-            println!("{}", "📝 Creating post...".cyan());
+            output::status("📝 Creating post...".cyan());
 
             match client.create_post(&title, &actual_content, submolt.as_deref()).await {
                 Ok(post) => {
+                    if !output::is_human() {
+                        output::emit(&post);
+                        return Ok(());
+                    }
+
                     println!("\n{}", "✓ Post created!".green().bold());
                     println!("{}", "━".repeat(40));
                     println!("Title: {}", post.title.bold());
@@ -312,56 +1371,54 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
                     }
                     println!("URL: https://www.moltbook.com/post/{}", post.id);
                 }
-                Err(e) => {
-                    eprintln!("{} {}", "Error:".red(), e);
-                }
+                Err(e) => fail(e),
             }
         }
 
-        Commands::Upvote { api_key, post_id } => {
-            let client = MoltbookClient::new(api_key);
-            println!("{}", "👍 Upvoting...".cyan());
+        Commands::Upvote { api_key, post_id, history } => {
+            let client = connect(&api_base, api_key);
+            output::status("👍 Upvoting...".cyan());
 
             match client.upvote(&post_id).await {
                 Ok(()) => {
-                    println!("{} Post {} upvoted!", "✓".green(), post_id);
-                }
-                Err(e) => {
-                    eprintln!("{} {}", "Error:".red(), e);
+                    record_vote(&history, &post_id, Vote::Upvote);
+                    output::status(format!("{} Post {} upvoted!", "✓".green(), post_id));
+                    output::emit(&serde_json::json!({ "post_id": post_id, "action": "upvote" }));
                 }
+                Err(e) => fail(e),
             }
         }
 
-        Commands::Downvote { api_key, post_id } => {
-            let client = MoltbookClient::new(api_key);
-            println!("{}", "👎 Downvoting...".cyan());
+        Commands::Downvote { api_key, post_id, history } => {
+            let client = connect(&api_base, api_key);
+            output::status("👎 Downvoting...".cyan());
 
             match client.downvote(&post_id).await {
                 Ok(()) => {
-                    println!("{} Post {} downvoted!", "✓".green(), post_id);
-                }
-                Err(e) => {
-                    eprintln!("{} {}", "Error:".red(), e);
+                    record_vote(&history, &post_id, Vote::Downvote);
+                    output::status(format!("{} Post {} downvoted!", "✓".green(), post_id));
+                    output::emit(&serde_json::json!({ "post_id": post_id, "action": "downvote" }));
                 }
+                Err(e) => fail(e),
             }
         }
 
-        Commands::Unvote { api_key, post_id } => {
-            let client = MoltbookClient::new(api_key);
-            println!("{}", "↩ Removing vote...".cyan());
+        Commands::Unvote { api_key, post_id, history } => {
+            let client = connect(&api_base, api_key);
+            output::status("↩ Removing vote...".cyan());
 
             match client.unvote(&post_id).await {
                 Ok(()) => {
-                    println!("{} Vote removed from post {}!", "✓".green(), post_id);
-                }
-                Err(e) => {
-                    eprintln!("{} {}", "Error:".red(), e);
+                    record_vote(&history, &post_id, Vote::Unvote);
+                    output::status(format!("{} Vote removed from post {}!", "✓".green(), post_id));
+                    output::emit(&serde_json::json!({ "post_id": post_id, "action": "unvote" }));
                 }
+                Err(e) => fail(e),
             }
         }
 
         Commands::Comment { api_key, post_id, message } => {
-            let client = MoltbookClient::new(api_key);
+            let client = connect(&api_base, api_key);
             
             // Support reading from stdin
             let actual_message = if message == "-" {
@@ -372,51 +1429,94 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
                 message
             };
 
-            println!("{}", "💬 Adding comment...".cyan());
+            output::status("💬 Adding comment...".cyan());
 
             match client.comment(&post_id, &actual_message).await {
                 Ok(comment) => {
+                    if !output::is_human() {
+                        output::emit(&comment);
+                        return Ok(());
+                    }
+
                     println!("\n{}", "✓ Comment added!".green().bold());
                     println!("{}", "━".repeat(40));
                     println!("ID: {}", comment.id.cyan());
                     println!("Content: {}", comment.content);
                 }
-                Err(e) => {
-                    eprintln!("{} {}", "Error:".red(), e);
-                }
+                Err(e) => fail(e),
             }
         }
 
-        Commands::Comments { api_key, post_id } => {
-            let client = MoltbookClient::new(api_key);
-            println!("{}", "💬 Fetching comments...".cyan());
+        Commands::Comments { api_key, post_id, show_spam, min_score, sanitize, filter } => {
+            let filter = load_filter(&filter);
+            let min_score = min_score.unwrap_or(filter.threshold());
+            let client = connect(&api_base, api_key);
+            output::status("💬 Fetching comments...".cyan());
+
+            // The post gives replies their context; comments are still scored without it
+            let parent = client
+                .get_post(&post_id)
+                .await
+                .ok()
+                .map(|post| PostView::new(&post.title, &post.content, post.author.as_deref()));
 
             match client.get_comments(&post_id).await {
                 Ok(comments) => {
-                    if comments.is_empty() {
+                    let total = comments.len();
+                    let mut hidden = 0;
+                    let mut entries = Vec::new();
+                    for mut comment in comments {
+                        let mut analysis =
+                            filter.analyze_comment(&comment.content, comment.author.as_deref(), parent.clone());
+                        if analysis.score >= min_score || show_spam {
+                            let sanitized = sanitize.comment(&mut comment, &mut analysis);
+                            entries.push(AnalyzedComment { comment, analysis, sanitized });
+                        } else {
+                            hidden += 1;
+                        }
+                    }
+
+                    if !output::is_human() {
+                        output::emit_list(&entries);
+                        return Ok(());
+                    }
+
+                    if total == 0 {
                         println!("\nNo comments yet.");
                     } else {
-                        println!("\n{} comments:\n", comments.len());
-                        for comment in comments {
+                        println!("\n{} comments:\n", total);
+                        for AnalyzedComment { comment, analysis, sanitized } in entries {
                             println!("{}", "━".repeat(40).dimmed());
+                            let status = if analysis.is_spam {
+                                format!("🚫 SPAM {}", analysis.score).red()
+                            } else {
+                                format!("✓ {}", analysis.score).green()
+                            };
                             println!(
-                                "{} • {} upvotes",
+                                "{} • {} upvotes • {}",
                                 comment.author.as_deref().unwrap_or("anon").cyan(),
-                                comment.upvotes
+                                comment.upvotes,
+                                status
                             );
                             println!("{}", comment.content);
+                            if !analysis.flags.is_empty() {
+                                println!("    Flags: {}", analysis.flags.join(", ").dimmed());
+                            }
+                            print_changes(sanitized.as_deref().unwrap_or_default());
+                        }
+                        if hidden > 0 {
+                            println!("{}", "━".repeat(40).dimmed());
+                            println!("{} hidden as spam (use --show-spam to see them)", hidden.to_string().red());
                         }
                     }
                 }
-                Err(e) => {
-                    eprintln!("{} {}", "Error:".red(), e);
-                }
+                Err(e) => fail(e),
             }
         }
 
         Commands::Profile { api_key, user } => {
-            let client = MoltbookClient::new(api_key);
-            println!("{}", "👤 Fetching profile...".cyan());
+            let client = connect(&api_base, api_key);
+            output::status("👤 Fetching profile...".cyan());
 
             let result = match user {
                 Some(username) => client.get_profile(&username).await,
@@ -425,6 +1525,11 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
 
             match result {
                 Ok(profile) => {
+                    if !output::is_human() {
+                        output::emit(&profile);
+                        return Ok(());
+                    }
+
                     println!("\n{}", "━".repeat(40));
                     println!("👤 {}", profile.name.bold());
                     println!("{}", "━".repeat(40));
@@ -437,20 +1542,25 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
                         println!("\n   Bio: {}", bio.dimmed());
                     }
                 }
-                Err(e) => {
-                    eprintln!("{} {}", "Error:".red(), e);
-                }
+                Err(e) => fail(e),
             }
         }
 
-        Commands::View { api_key, post_id } => {
-            let client = MoltbookClient::new(api_key);
-            println!("{}", "📖 Fetching post...".cyan());
+        Commands::View { api_key, post_id, sanitize, filter } => {
+            let filter = load_filter(&filter);
+            let client = connect(&api_base, api_key);
+            output::status("📖 Fetching post...".cyan());
 
             match client.get_post(&post_id).await {
-                Ok(post) => {
-                    let analysis = filter.analyze(&post.title, &post.content, post.author.as_deref());
-                    
+                Ok(mut post) => {
+                    let mut analysis = filter.analyze(&post.title, &post.content, post.author.as_deref());
+                    let sanitized = sanitize.post(&mut post, &mut analysis);
+
+                    if !output::is_human() {
+                        output::emit(&AnalyzedPost { post, analysis, sanitized });
+                        return Ok(());
+                    }
+
                     println!("\n{}", "━".repeat(60));
                     println!("{}", post.title.bold());
                     println!(
@@ -470,13 +1580,22 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
                         if analysis.is_spam { "(spam)".red() } else { "".normal() }
                     );
                     println!("URL: https://www.moltbook.com/post/{}", post.id);
+                    print_changes(sanitized.as_deref().unwrap_or_default());
                 }
-                Err(e) => {
-                    eprintln!("{} {}", "Error:".red(), e);
-                }
+                Err(e) => fail(e),
             }
         }
     }
 
     Ok(())
 }
+
+/// One-line summary of a rule for the `rules` command, e.g. "Crypto shilling (-35/-15)"
+fn describe_rule(rule: &dyn filter::Rule) -> String {
+    let weights: Vec<String> = rule.weights().iter().map(|w| format!("{:+}", w)).collect();
+    if weights.is_empty() {
+        rule.description().to_string()
+    } else {
+        format!("{} ({})", rule.description(), weights.join("/"))
+    }
+}
@@ -1,10 +1,27 @@
-use reqwest::header::{HeaderMap, HeaderValue, AUTHORIZATION, CONTENT_TYPE};
+use chrono::{DateTime, Utc};
+use reqwest::header::{HeaderMap, HeaderValue, AUTHORIZATION};
+use reqwest::{RequestBuilder, Response, StatusCode};
+use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
+use std::time::Duration;
 
-const MOLTBOOK_API_BASE: &str = "https://www.moltbook.com/api/v1";
+mod error;
+mod pagination;
+mod profiles;
+mod retry;
+
+pub use error::MoltbookError;
+pub use pagination::{FeedQuery, FeedSource};
+pub use profiles::ProfileCache;
+pub use retry::{Attempt, RetryPolicy};
+
+use pagination::encode_segment;
+
+/// Production API base URL
+pub const DEFAULT_API_BASE: &str = "https://www.moltbook.com/api/v1";
 
 /// User profile structure
-#[derive(Debug, Deserialize, Serialize)]
+#[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct Profile {
     pub id: String,
     pub name: String,
@@ -87,6 +104,25 @@ struct RawPost {
     created_at: Option<String>,
 }
 
+/// An API timestamp parsed as an RFC 3339 time
+fn parse_time(raw: Option<&str>) -> Option<DateTime<Utc>> {
+    DateTime::parse_from_rfc3339(raw?).ok().map(|t| t.with_timezone(&Utc))
+}
+
+impl Profile {
+    /// `created_at` parsed as an RFC 3339 time
+    pub fn created(&self) -> Option<DateTime<Utc>> {
+        parse_time(self.created_at.as_deref())
+    }
+}
+
+impl Post {
+    /// `created_at` parsed as an RFC 3339 time
+    pub fn created(&self) -> Option<DateTime<Utc>> {
+        parse_time(self.created_at.as_deref())
+    }
+}
+
 impl From<RawPost> for Post {
     fn from(raw: RawPost) -> Self {
         Post {
@@ -108,22 +144,103 @@ struct FeedResponse {
     success: bool,
     posts: Option<Vec<RawPost>>,
     error: Option<String>,
+    #[serde(default)]
+    next_cursor: Option<String>,
+    #[serde(default)]
+    has_more: Option<bool>,
 }
 
 /// Client for interacting with Moltbook API
 pub struct MoltbookClient {
     client: reqwest::Client,
     api_key: String,
+    base_url: String,
+    retry: RetryPolicy,
+}
+
+/// Builder for [`MoltbookClient`] with non-default settings
+pub struct MoltbookClientBuilder {
+    api_key: String,
+    base_url: String,
+    timeout: Duration,
+    user_agent: Option<String>,
+    default_headers: HeaderMap,
+    retry: RetryPolicy,
+}
+
+impl MoltbookClientBuilder {
+    pub fn api_key(mut self, api_key: impl Into<String>) -> Self {
+        self.api_key = api_key.into();
+        self
+    }
+
+    /// API root, e.g. a staging instance or a local mock server
+    pub fn base_url(mut self, base_url: impl Into<String>) -> Self {
+        self.base_url = base_url.into().trim_end_matches('/').to_string();
+        self
+    }
+
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    pub fn user_agent(mut self, user_agent: impl Into<String>) -> Self {
+        self.user_agent = Some(user_agent.into());
+        self
+    }
+
+    /// Headers sent with every request
+    pub fn default_headers(mut self, headers: HeaderMap) -> Self {
+        self.default_headers = headers;
+        self
+    }
+
+    /// How failed requests are retried; see [`RetryPolicy`]
+    pub fn retry_policy(mut self, policy: RetryPolicy) -> Self {
+        self.retry = policy;
+        self
+    }
+
+    pub fn build(self) -> Result<MoltbookClient, MoltbookError> {
+        let mut builder = reqwest::Client::builder()
+            .timeout(self.timeout)
+            .default_headers(self.default_headers);
+        if let Some(user_agent) = self.user_agent {
+            builder = builder.user_agent(user_agent);
+        }
+
+        Ok(MoltbookClient {
+            client: builder.build()?,
+            api_key: self.api_key,
+            base_url: self.base_url,
+            retry: self.retry,
+        })
+    }
 }
 
 impl MoltbookClient {
     pub fn new(api_key: String) -> Self {
-        let client = reqwest::Client::builder()
-            .timeout(std::time::Duration::from_secs(30))
+        Self::builder()
+            .api_key(api_key)
             .build()
-            .expect("Failed to create HTTP client");
+            .expect("Failed to create HTTP client")
+    }
 
-        Self { client, api_key }
+    pub fn builder() -> MoltbookClientBuilder {
+        MoltbookClientBuilder {
+            api_key: String::new(),
+            base_url: DEFAULT_API_BASE.to_string(),
+            timeout: Duration::from_secs(30),
+            user_agent: Some(concat!("moltbook_filter/", env!("CARGO_PKG_VERSION")).to_string()),
+            default_headers: HeaderMap::new(),
+            retry: RetryPolicy::default(),
+        }
+    }
+
+    /// API root this client sends requests to
+    pub fn base_url(&self) -> &str {
+        &self.base_url
     }
 
     fn auth_headers(&self) -> HeaderMap {
@@ -136,53 +253,78 @@ impl MoltbookClient {
         headers
     }
 
-    /// Fetch the feed with specified sort and limit
-    pub async fn get_feed(&self, sort: &str, limit: u32) -> Result<Vec<Post>, String> {
-        let url = format!("{}/posts?sort={}&limit={}", MOLTBOOK_API_BASE, sort, limit);
-
-        let response = self
-            .client
-            .get(&url)
-            .headers(self.auth_headers())
-            .send()
-            .await
-            .map_err(|e| format!("Request failed: {}", e))?;
-
-        if !response.status().is_success() {
-            return Err(format!("API returned status: {}", response.status()));
-        }
+    async fn get(&self, url: &str) -> Result<Response, MoltbookError> {
+        self.send(false, || self.client.get(url).headers(self.auth_headers())).await
+    }
 
-        let feed: FeedResponse = response
-            .json()
-            .await
-            .map_err(|e| format!("Failed to parse response: {}", e))?;
+    /// GET with query parameters, encoded so values can't break the URL
+    async fn get_query(&self, url: &str, query: &[(&str, String)]) -> Result<Response, MoltbookError> {
+        self.send(false, || self.client.get(url).query(query).headers(self.auth_headers())).await
+    }
+
+    async fn post(&self, url: &str) -> Result<Response, MoltbookError> {
+        self.send(true, || self.client.post(url).headers(self.auth_headers())).await
+    }
+
+    async fn post_json<B: Serialize>(&self, url: &str, body: &B) -> Result<Response, MoltbookError> {
+        self.send(true, || self.client.post(url).headers(self.auth_headers()).json(body)).await
+    }
 
-        if !feed.success {
-            return Err(feed.error.unwrap_or_else(|| "Unknown error".to_string()));
+    /// Send a request, retrying according to the client's [`RetryPolicy`].
+    /// Returns the first successful response, or the last error together with
+    /// the attempts that preceded it.
+    async fn send(
+        &self,
+        write: bool,
+        build: impl Fn() -> RequestBuilder,
+    ) -> Result<Response, MoltbookError> {
+        let max_attempts = self.retry.attempts_for(write);
+        let mut attempts = Vec::new();
+
+        loop {
+            let (error, retry_after) = match build().send().await {
+                Ok(response) if response.status().is_success() => return Ok(response),
+                Ok(response) => {
+                    let retry_after = if retry::honors_retry_after(response.status()) {
+                        retry::retry_after(response.headers())
+                    } else {
+                        None
+                    };
+                    (status_error(response).await, retry_after)
+                }
+                Err(e) => (e.into(), None),
+            };
+
+            let attempt = attempts.len() as u32 + 1;
+            let delay = if attempt < max_attempts && retry::is_retryable(&error) {
+                self.retry.delay(attempt, retry_after)
+            } else {
+                None
+            };
+
+            let Some(delay) = delay else {
+                return Err(if attempts.is_empty() {
+                    error
+                } else {
+                    MoltbookError::RetriesExhausted { attempts, last: Box::new(error) }
+                });
+            };
+
+            attempts.push(Attempt { error, delay });
+            tokio::time::sleep(delay).await;
         }
+    }
 
-        Ok(feed.posts
-            .unwrap_or_default()
-            .into_iter()
-            .map(Post::from)
-            .collect())
+    /// Fetch the feed with specified sort and limit
+    pub async fn get_feed(&self, sort: &str, limit: u32) -> Result<Vec<Post>, MoltbookError> {
+        Ok(self.fetch_page(&FeedSource::Global.path(), sort, limit, None).await?.posts)
     }
 
     /// Fetch a specific post by ID
-    pub async fn get_post(&self, post_id: &str) -> Result<Post, String> {
-        let url = format!("{}/posts/{}", MOLTBOOK_API_BASE, post_id);
-
-        let response = self
-            .client
-            .get(&url)
-            .headers(self.auth_headers())
-            .send()
-            .await
-            .map_err(|e| format!("Request failed: {}", e))?;
-
-        if !response.status().is_success() {
-            return Err(format!("API returned status: {}", response.status()));
-        }
+    pub async fn get_post(&self, post_id: &str) -> Result<Post, MoltbookError> {
+        let url = format!("{}/posts/{}", self.base_url, post_id);
+
+        let response = self.get(&url).await?;
 
         #[derive(Deserialize)]
         struct PostResponse {
@@ -191,55 +333,35 @@ impl MoltbookClient {
             error: Option<String>,
         }
 
-        let resp: PostResponse = response
-            .json()
-            .await
-            .map_err(|e| format!("Failed to parse response: {}", e))?;
+        let resp: PostResponse = read_json(response).await?;
 
-        if !resp.success {
-            return Err(resp.error.unwrap_or_else(|| "Unknown error".to_string()));
-        }
+        check_success(resp.success, resp.error)?;
 
         resp.post
             .map(Post::from)
-            .ok_or_else(|| "Post not found".to_string())
+            .ok_or(MoltbookError::NotFound)
     }
 
     /// Get personalized feed (from subscriptions + following)
-    pub async fn get_personalized_feed(&self, sort: &str, limit: u32) -> Result<Vec<Post>, String> {
-        let url = format!("{}/feed?sort={}&limit={}", MOLTBOOK_API_BASE, sort, limit);
-
-        let response = self
-            .client
-            .get(&url)
-            .headers(self.auth_headers())
-            .send()
-            .await
-            .map_err(|e| format!("Request failed: {}", e))?;
-
-        if !response.status().is_success() {
-            return Err(format!("API returned status: {}", response.status()));
-        }
-
-        let feed: FeedResponse = response
-            .json()
-            .await
-            .map_err(|e| format!("Failed to parse response: {}", e))?;
+    pub async fn get_personalized_feed(&self, sort: &str, limit: u32) -> Result<Vec<Post>, MoltbookError> {
+        Ok(self.fetch_page(&FeedSource::Personal.path(), sort, limit, None).await?.posts)
+    }
 
-        if !feed.success {
-            return Err(feed.error.unwrap_or_else(|| "Unknown error".to_string()));
-        }
+    /// Fetch the newest posts of a submolt
+    pub async fn get_submolt_feed(&self, submolt: &str, sort: &str, limit: u32) -> Result<Vec<Post>, MoltbookError> {
+        let path = FeedSource::Submolt(submolt.to_string()).path();
+        Ok(self.fetch_page(&path, sort, limit, None).await?.posts)
+    }
 
-        Ok(feed.posts
-            .unwrap_or_default()
-            .into_iter()
-            .map(Post::from)
-            .collect())
+    /// Fetch posts written by an agent
+    pub async fn get_user_posts(&self, username: &str, sort: &str, limit: u32) -> Result<Vec<Post>, MoltbookError> {
+        let path = FeedSource::User(username.to_string()).path();
+        Ok(self.fetch_page(&path, sort, limit, None).await?.posts)
     }
 
     /// Create a new post
-    pub async fn create_post(&self, title: &str, content: &str, submolt: Option<&str>) -> Result<Post, String> {
-        let url = format!("{}/posts", MOLTBOOK_API_BASE);
+    pub async fn create_post(&self, title: &str, content: &str, submolt: Option<&str>) -> Result<Post, MoltbookError> {
+        let url = format!("{}/posts", self.base_url);
 
         #[derive(Serialize)]
         struct CreatePostRequest<'a> {
@@ -251,23 +373,7 @@ impl MoltbookClient {
 
         let body = CreatePostRequest { title, content, submolt_name: submolt };
 
-        let mut headers = self.auth_headers();
-        headers.insert(CONTENT_TYPE, HeaderValue::from_static("application/json"));
-
-        let response = self
-            .client
-            .post(&url)
-            .headers(headers)
-            .json(&body)
-            .send()
-            .await
-            .map_err(|e| format!("Request failed: {}", e))?;
-
-        if !response.status().is_success() {
-            let status = response.status();
-            let body = response.text().await.unwrap_or_default();
-            return Err(format!("API returned status {}: {}", status, body));
-        }
+        let response = self.post_json(&url, &body).await?;
 
         #[derive(Deserialize)]
         struct CreatePostResponse {
@@ -276,51 +382,34 @@ impl MoltbookClient {
             error: Option<String>,
         }
 
-        let resp: CreatePostResponse = response
-            .json()
-            .await
-            .map_err(|e| format!("Failed to parse response: {}", e))?;
+        let resp: CreatePostResponse = read_json(response).await?;
 
-        if !resp.success {
-            return Err(resp.error.unwrap_or_else(|| "Unknown error".to_string()));
-        }
+        check_success(resp.success, resp.error)?;
 
         resp.post
             .map(Post::from)
-            .ok_or_else(|| "No post in response".to_string())
+            .ok_or_else(|| missing("post"))
     }
 
     /// Upvote a post
-    pub async fn upvote(&self, post_id: &str) -> Result<(), String> {
+    pub async fn upvote(&self, post_id: &str) -> Result<(), MoltbookError> {
         self.vote(post_id, "upvote").await
     }
 
     /// Downvote a post
-    pub async fn downvote(&self, post_id: &str) -> Result<(), String> {
+    pub async fn downvote(&self, post_id: &str) -> Result<(), MoltbookError> {
         self.vote(post_id, "downvote").await
     }
 
     /// Remove vote from a post
-    pub async fn unvote(&self, post_id: &str) -> Result<(), String> {
+    pub async fn unvote(&self, post_id: &str) -> Result<(), MoltbookError> {
         self.vote(post_id, "unvote").await
     }
 
-    async fn vote(&self, post_id: &str, action: &str) -> Result<(), String> {
-        let url = format!("{}/posts/{}/{}", MOLTBOOK_API_BASE, post_id, action);
+    async fn vote(&self, post_id: &str, action: &str) -> Result<(), MoltbookError> {
+        let url = format!("{}/posts/{}/{}", self.base_url, post_id, action);
 
-        let response = self
-            .client
-            .post(&url)
-            .headers(self.auth_headers())
-            .send()
-            .await
-            .map_err(|e| format!("Request failed: {}", e))?;
-
-        if !response.status().is_success() {
-            let status = response.status();
-            let body = response.text().await.unwrap_or_default();
-            return Err(format!("API returned status {}: {}", status, body));
-        }
+        let response = self.post(&url).await?;
 
         #[derive(Deserialize)]
         struct VoteResponse {
@@ -328,21 +417,16 @@ impl MoltbookClient {
             error: Option<String>,
         }
 
-        let resp: VoteResponse = response
-            .json()
-            .await
-            .map_err(|e| format!("Failed to parse response: {}", e))?;
+        let resp: VoteResponse = read_json(response).await?;
 
-        if !resp.success {
-            return Err(resp.error.unwrap_or_else(|| "Unknown error".to_string()));
-        }
+        check_success(resp.success, resp.error)?;
 
         Ok(())
     }
 
     /// Add a comment to a post
-    pub async fn comment(&self, post_id: &str, content: &str) -> Result<Comment, String> {
-        let url = format!("{}/posts/{}/comments", MOLTBOOK_API_BASE, post_id);
+    pub async fn comment(&self, post_id: &str, content: &str) -> Result<Comment, MoltbookError> {
+        let url = format!("{}/posts/{}/comments", self.base_url, post_id);
 
         #[derive(Serialize)]
         struct CommentRequest<'a> {
@@ -351,23 +435,7 @@ impl MoltbookClient {
 
         let body = CommentRequest { content };
 
-        let mut headers = self.auth_headers();
-        headers.insert(CONTENT_TYPE, HeaderValue::from_static("application/json"));
-
-        let response = self
-            .client
-            .post(&url)
-            .headers(headers)
-            .json(&body)
-            .send()
-            .await
-            .map_err(|e| format!("Request failed: {}", e))?;
-
-        if !response.status().is_success() {
-            let status = response.status();
-            let body = response.text().await.unwrap_or_default();
-            return Err(format!("API returned status {}: {}", status, body));
-        }
+        let response = self.post_json(&url, &body).await?;
 
         #[derive(Deserialize)]
         struct RawComment {
@@ -386,14 +454,9 @@ impl MoltbookClient {
             error: Option<String>,
         }
 
-        let resp: CommentResponse = response
-            .json()
-            .await
-            .map_err(|e| format!("Failed to parse response: {}", e))?;
+        let resp: CommentResponse = read_json(response).await?;
 
-        if !resp.success {
-            return Err(resp.error.unwrap_or_else(|| "Unknown error".to_string()));
-        }
+        check_success(resp.success, resp.error)?;
 
         resp.comment
             .map(|c| Comment {
@@ -403,24 +466,14 @@ impl MoltbookClient {
                 upvotes: c.upvotes,
                 created_at: c.created_at,
             })
-            .ok_or_else(|| "No comment in response".to_string())
+            .ok_or_else(|| missing("comment"))
     }
 
     /// Get comments on a post
-    pub async fn get_comments(&self, post_id: &str) -> Result<Vec<Comment>, String> {
-        let url = format!("{}/posts/{}/comments", MOLTBOOK_API_BASE, post_id);
-
-        let response = self
-            .client
-            .get(&url)
-            .headers(self.auth_headers())
-            .send()
-            .await
-            .map_err(|e| format!("Request failed: {}", e))?;
-
-        if !response.status().is_success() {
-            return Err(format!("API returned status: {}", response.status()));
-        }
+    pub async fn get_comments(&self, post_id: &str) -> Result<Vec<Comment>, MoltbookError> {
+        let url = format!("{}/posts/{}/comments", self.base_url, post_id);
+
+        let response = self.get(&url).await?;
 
         #[derive(Deserialize)]
         struct RawComment {
@@ -439,14 +492,9 @@ impl MoltbookClient {
             error: Option<String>,
         }
 
-        let resp: CommentsResponse = response
-            .json()
-            .await
-            .map_err(|e| format!("Failed to parse response: {}", e))?;
+        let resp: CommentsResponse = read_json(response).await?;
 
-        if !resp.success {
-            return Err(resp.error.unwrap_or_else(|| "Unknown error".to_string()));
-        }
+        check_success(resp.success, resp.error)?;
 
         Ok(resp.comments
             .unwrap_or_default()
@@ -462,20 +510,10 @@ impl MoltbookClient {
     }
 
     /// Get the authenticated user's profile
-    pub async fn get_my_profile(&self) -> Result<Profile, String> {
-        let url = format!("{}/users/me", MOLTBOOK_API_BASE);
-
-        let response = self
-            .client
-            .get(&url)
-            .headers(self.auth_headers())
-            .send()
-            .await
-            .map_err(|e| format!("Request failed: {}", e))?;
-
-        if !response.status().is_success() {
-            return Err(format!("API returned status: {}", response.status()));
-        }
+    pub async fn get_my_profile(&self) -> Result<Profile, MoltbookError> {
+        let url = format!("{}/users/me", self.base_url);
+
+        let response = self.get(&url).await?;
 
         #[derive(Deserialize)]
         struct ProfileResponse {
@@ -484,34 +522,19 @@ impl MoltbookClient {
             error: Option<String>,
         }
 
-        let resp: ProfileResponse = response
-            .json()
-            .await
-            .map_err(|e| format!("Failed to parse response: {}", e))?;
+        let resp: ProfileResponse = read_json(response).await?;
 
-        if !resp.success {
-            return Err(resp.error.unwrap_or_else(|| "Unknown error".to_string()));
-        }
+        check_success(resp.success, resp.error)?;
 
         resp.user
-            .ok_or_else(|| "No user in response".to_string())
+            .ok_or_else(|| missing("user"))
     }
 
     /// Get a user's profile by name
-    pub async fn get_profile(&self, username: &str) -> Result<Profile, String> {
-        let url = format!("{}/users/{}", MOLTBOOK_API_BASE, username);
-
-        let response = self
-            .client
-            .get(&url)
-            .headers(self.auth_headers())
-            .send()
-            .await
-            .map_err(|e| format!("Request failed: {}", e))?;
-
-        if !response.status().is_success() {
-            return Err(format!("API returned status: {}", response.status()));
-        }
+    pub async fn get_profile(&self, username: &str) -> Result<Profile, MoltbookError> {
+        let url = format!("{}/users/{}", self.base_url, encode_segment(username));
+
+        let response = self.get(&url).await?;
 
         #[derive(Deserialize)]
         struct ProfileResponse {
@@ -520,16 +543,49 @@ impl MoltbookClient {
             error: Option<String>,
         }
 
-        let resp: ProfileResponse = response
-            .json()
-            .await
-            .map_err(|e| format!("Failed to parse response: {}", e))?;
+        let resp: ProfileResponse = read_json(response).await?;
 
-        if !resp.success {
-            return Err(resp.error.unwrap_or_else(|| "Unknown error".to_string()));
-        }
+        check_success(resp.success, resp.error)?;
 
         resp.user
-            .ok_or_else(|| "User not found".to_string())
+            .ok_or(MoltbookError::NotFound)
+    }
+}
+
+/// Decode a successful JSON response
+async fn read_json<T: DeserializeOwned>(response: Response) -> Result<T, MoltbookError> {
+    let body = response.text().await?;
+    Ok(serde_json::from_str(&body)?)
+}
+
+/// Map an error status to [`MoltbookError`]
+async fn status_error(response: Response) -> MoltbookError {
+    match response.status() {
+        StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN => MoltbookError::Unauthorized,
+        StatusCode::NOT_FOUND => MoltbookError::NotFound,
+        StatusCode::TOO_MANY_REQUESTS => MoltbookError::RateLimited {
+            retry_after: retry::retry_after(response.headers()),
+        },
+        status => MoltbookError::Http {
+            status,
+            body: response.text().await.unwrap_or_default(),
+        },
+    }
+}
+
+/// Check the `success`/`error` envelope every endpoint returns
+fn check_success(success: bool, error: Option<String>) -> Result<(), MoltbookError> {
+    if success {
+        Ok(())
+    } else {
+        Err(MoltbookError::Api {
+            message: error.unwrap_or_else(|| "Unknown error".to_string()),
+        })
+    }
+}
+
+fn missing(what: &str) -> MoltbookError {
+    MoltbookError::Api {
+        message: format!("No {} in response", what),
     }
 }
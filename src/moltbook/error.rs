@@ -0,0 +1,98 @@
+use reqwest::StatusCode;
+use std::fmt;
+use std::time::Duration;
+
+use super::Attempt;
+
+/// Error returned by [`MoltbookClient`](super::MoltbookClient) methods
+#[derive(Debug)]
+pub enum MoltbookError {
+    /// The request never got a response (connection, DNS, TLS...)
+    Transport(reqwest::Error),
+    /// The request or reading its response took longer than the client's timeout
+    Timeout(reqwest::Error),
+    /// The API answered with an unexpected status
+    Http { status: StatusCode, body: String },
+    /// 429 Too Many Requests
+    RateLimited { retry_after: Option<Duration> },
+    /// 401/403, usually a missing or invalid API key
+    Unauthorized,
+    /// 404, or the API returned no object for the requested ID
+    NotFound,
+    /// The response body wasn't the JSON we expected
+    Decode(serde_json::Error),
+    /// The API answered `success: false`
+    Api { message: String },
+    /// Every attempt allowed by the retry policy failed
+    RetriesExhausted { attempts: Vec<Attempt>, last: Box<MoltbookError> },
+}
+
+impl MoltbookError {
+    /// Whether the request timed out
+    pub fn is_timeout(&self) -> bool {
+        matches!(self.root(), MoltbookError::Timeout(_))
+    }
+
+    /// The final error, looking through [`MoltbookError::RetriesExhausted`]
+    pub fn root(&self) -> &MoltbookError {
+        match self {
+            MoltbookError::RetriesExhausted { last, .. } => last.root(),
+            other => other,
+        }
+    }
+}
+
+impl fmt::Display for MoltbookError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MoltbookError::Timeout(e) => write!(f, "Request timed out: {}", e),
+            MoltbookError::Transport(e) => write!(f, "Request failed: {}", e),
+            MoltbookError::Http { status, body } if body.is_empty() => {
+                write!(f, "API returned status {}", status)
+            }
+            MoltbookError::Http { status, body } => write!(f, "API returned status {}: {}", status, body),
+            MoltbookError::RateLimited { retry_after: Some(after) } => {
+                write!(f, "Rate limited, retry after {}s", after.as_secs())
+            }
+            MoltbookError::RateLimited { retry_after: None } => write!(f, "Rate limited"),
+            MoltbookError::Unauthorized => write!(f, "Unauthorized: check your API key"),
+            MoltbookError::NotFound => write!(f, "Not found"),
+            MoltbookError::Decode(e) => write!(f, "Failed to parse response: {}", e),
+            MoltbookError::Api { message } => write!(f, "{}", message),
+            MoltbookError::RetriesExhausted { attempts, last } => {
+                write!(f, "{} (gave up after {} attempts", last, attempts.len() + 1)?;
+                for (i, attempt) in attempts.iter().enumerate() {
+                    write!(f, "; #{}: {}, waited {:.1}s", i + 1, attempt.error, attempt.delay.as_secs_f64())?;
+                }
+                write!(f, ")")
+            }
+        }
+    }
+}
+
+impl std::error::Error for MoltbookError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            MoltbookError::Transport(e) | MoltbookError::Timeout(e) => Some(e),
+            MoltbookError::Decode(e) => Some(e),
+            MoltbookError::RetriesExhausted { last, .. } => Some(last.as_ref()),
+            _ => None,
+        }
+    }
+}
+
+impl From<reqwest::Error> for MoltbookError {
+    fn from(e: reqwest::Error) -> Self {
+        if e.is_timeout() {
+            MoltbookError::Timeout(e)
+        } else {
+            MoltbookError::Transport(e)
+        }
+    }
+}
+
+impl From<serde_json::Error> for MoltbookError {
+    fn from(e: serde_json::Error) -> Self {
+        MoltbookError::Decode(e)
+    }
+}
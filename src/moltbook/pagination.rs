@@ -0,0 +1,277 @@
+use chrono::{DateTime, Utc};
+use futures_util::stream::{self, Stream};
+use std::collections::VecDeque;
+use std::fmt;
+use std::str::FromStr;
+
+use super::{check_success, read_json, FeedResponse, MoltbookClient, MoltbookError, Post};
+
+/// Largest page requested from the API
+const MAX_PAGE_SIZE: u32 = 100;
+
+/// Which timeline a feed is read from
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub enum FeedSource {
+    /// Every post on Moltbook
+    #[default]
+    Global,
+    /// Posts from subscribed submolts and followed agents
+    Personal,
+    /// Posts in one submolt
+    Submolt(String),
+    /// Posts by one agent
+    User(String),
+}
+
+impl FeedSource {
+    /// API path listing the source's posts
+    pub(crate) fn path(&self) -> String {
+        match self {
+            FeedSource::Global => "/posts".to_string(),
+            FeedSource::Personal => "/feed".to_string(),
+            FeedSource::Submolt(name) => format!("/submolts/{}/feed", encode_segment(name)),
+            FeedSource::User(name) => format!("/users/{}/posts", encode_segment(name)),
+        }
+    }
+}
+
+/// Percent-encode a name for use as one URL path segment
+pub(crate) fn encode_segment(name: &str) -> String {
+    name.bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => (b as char).to_string(),
+            _ => format!("%{:02X}", b),
+        })
+        .collect()
+}
+
+impl FromStr for FeedSource {
+    type Err = String;
+
+    /// Parse `global`, `personal`, `submolt:<name>` or `user:<name>`
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let valid_name = |name: &str| {
+            !name.is_empty() && name.chars().all(|c| c.is_alphanumeric() || c == '_' || c == '-')
+        };
+
+        match s.split_once(':') {
+            None if s == "global" => Ok(FeedSource::Global),
+            None if s == "personal" => Ok(FeedSource::Personal),
+            Some(("submolt", name)) if valid_name(name) => Ok(FeedSource::Submolt(name.to_string())),
+            Some(("user", name)) if valid_name(name) => Ok(FeedSource::User(name.to_string())),
+            Some(("submolt" | "user", name)) => Err(format!("invalid name `{}`", name)),
+            _ => Err(format!(
+                "unknown source `{}`, expected global, personal, submolt:<name> or user:<name>",
+                s
+            )),
+        }
+    }
+}
+
+impl fmt::Display for FeedSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FeedSource::Global => write!(f, "global"),
+            FeedSource::Personal => write!(f, "personal"),
+            FeedSource::Submolt(name) => write!(f, "submolt:{}", name),
+            FeedSource::User(name) => write!(f, "user:{}", name),
+        }
+    }
+}
+
+/// What [`MoltbookClient::feed_stream`] should fetch
+#[derive(Debug, Clone)]
+pub struct FeedQuery {
+    pub source: FeedSource,
+    pub sort: String,
+    /// Stop after this many posts
+    pub limit: Option<usize>,
+    /// Skip posts created before this time. With `sort = "new"` the stream
+    /// ends at the first older post.
+    pub since: Option<DateTime<Utc>>,
+    /// Posts requested per page
+    pub page_size: u32,
+}
+
+impl FeedQuery {
+    pub fn new(sort: impl Into<String>) -> Self {
+        Self {
+            source: FeedSource::Global,
+            sort: sort.into(),
+            limit: None,
+            since: None,
+            page_size: 50,
+        }
+    }
+
+    pub fn source(mut self, source: FeedSource) -> Self {
+        self.source = source;
+        self
+    }
+
+    pub fn limit(mut self, limit: usize) -> Self {
+        self.limit = Some(limit);
+        self
+    }
+
+    pub fn since(mut self, since: DateTime<Utc>) -> Self {
+        self.since = Some(since);
+        self
+    }
+
+    pub fn page_size(mut self, page_size: u32) -> Self {
+        self.page_size = page_size.clamp(1, MAX_PAGE_SIZE);
+        self
+    }
+
+    /// Page size for the next request, never more than what's left of the limit
+    fn next_page_size(&self, yielded: usize) -> u32 {
+        match self.limit {
+            Some(limit) => (limit.saturating_sub(yielded) as u32).clamp(1, self.page_size),
+            None => self.page_size,
+        }
+    }
+}
+
+/// Where the next page starts
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum PageCursor {
+    /// Opaque cursor returned by the API
+    Cursor(String),
+    /// Number of posts already read, for endpoints without cursors
+    Offset(usize),
+}
+
+pub(crate) struct Page {
+    pub posts: Vec<Post>,
+    pub next: Option<PageCursor>,
+}
+
+struct StreamState<'a> {
+    client: &'a MoltbookClient,
+    path: String,
+    query: FeedQuery,
+    cursor: Option<PageCursor>,
+    buffer: VecDeque<Post>,
+    yielded: usize,
+    done: bool,
+}
+
+impl MoltbookClient {
+    /// Stream posts from the query's source, following pages until the
+    /// query's limit, its `since` cutoff or the end of the feed.
+    pub fn feed_stream(&self, query: FeedQuery) -> impl Stream<Item = Result<Post, MoltbookError>> + '_ {
+        let state = StreamState {
+            client: self,
+            path: query.source.path(),
+            query,
+            cursor: None,
+            buffer: VecDeque::new(),
+            yielded: 0,
+            done: false,
+        };
+
+        stream::unfold(state, |mut st| async move {
+            loop {
+                if st.query.limit.is_some_and(|limit| st.yielded >= limit) {
+                    return None;
+                }
+
+                if let Some(post) = st.buffer.pop_front() {
+                    if let Some(since) = st.query.since {
+                        if post.created().is_some_and(|t| t < since) {
+                            if st.query.sort == "new" {
+                                return None;
+                            }
+                            continue;
+                        }
+                    }
+                    st.yielded += 1;
+                    return Some((Ok(post), st));
+                }
+
+                if st.done {
+                    return None;
+                }
+
+                let page_size = st.query.next_page_size(st.yielded);
+                match st
+                    .client
+                    .fetch_page(&st.path, &st.query.sort, page_size, st.cursor.as_ref())
+                    .await
+                {
+                    Ok(page) => {
+                        st.done = page.posts.is_empty() || page.next.is_none();
+                        st.cursor = page.next;
+                        st.buffer.extend(page.posts);
+                    }
+                    Err(e) => {
+                        st.done = true;
+                        return Some((Err(e), st));
+                    }
+                }
+            }
+        })
+    }
+
+    /// Fetch one page of a post listing
+    pub(crate) async fn fetch_page(
+        &self,
+        path: &str,
+        sort: &str,
+        limit: u32,
+        cursor: Option<&PageCursor>,
+    ) -> Result<Page, MoltbookError> {
+        let url = format!("{}{}", self.base_url, path);
+        let mut query = vec![("sort", sort.to_string()), ("limit", limit.to_string())];
+        let offset = match cursor {
+            Some(PageCursor::Cursor(c)) => {
+                query.push(("cursor", c.clone()));
+                0
+            }
+            Some(PageCursor::Offset(offset)) => {
+                query.push(("offset", offset.to_string()));
+                *offset
+            }
+            None => 0,
+        };
+
+        let response = self.get_query(&url, &query).await?;
+        let feed: FeedResponse = read_json(response).await?;
+        check_success(feed.success, feed.error)?;
+
+        let posts: Vec<Post> = feed.posts.unwrap_or_default().into_iter().map(Post::from).collect();
+        let next = match feed.next_cursor {
+            Some(cursor) => Some(PageCursor::Cursor(cursor)),
+            None if feed.has_more == Some(false) => None,
+            None if feed.has_more.is_none() && posts.len() < limit as usize => None,
+            None => Some(PageCursor::Offset(offset + posts.len())),
+        };
+
+        Ok(Page { posts, next })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_page_size_respects_limit() {
+        let query = FeedQuery::new("new").limit(120).page_size(50);
+        assert_eq!(query.next_page_size(0), 50);
+        assert_eq!(query.next_page_size(100), 20);
+        assert_eq!(FeedQuery::new("new").page_size(500).page_size, MAX_PAGE_SIZE);
+    }
+
+    #[test]
+    fn test_parse_source() {
+        assert_eq!("global".parse(), Ok(FeedSource::Global));
+        assert_eq!("personal".parse(), Ok(FeedSource::Personal));
+        assert_eq!("submolt:tech".parse(), Ok(FeedSource::Submolt("tech".to_string())));
+        assert_eq!("user:some_agent".parse(), Ok(FeedSource::User("some_agent".to_string())));
+        assert!("submolt:".parse::<FeedSource>().is_err());
+        assert!("user:../admin".parse::<FeedSource>().is_err());
+        assert!("everything".parse::<FeedSource>().is_err());
+    }
+}
@@ -0,0 +1,73 @@
+//! A local cache of author profiles, so enriching a feed doesn't fetch the
+//! same profiles on every run
+
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::Path;
+
+use super::Profile;
+use crate::jsonl::{write_lines, DataFileError};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Cached {
+    fetched_at: DateTime<Utc>,
+    profile: Profile,
+}
+
+/// Profiles keyed by lowercased name, each with the time it was fetched
+#[derive(Debug, Default)]
+pub struct ProfileCache {
+    profiles: HashMap<String, Cached>,
+}
+
+impl ProfileCache {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Read a cache file. It's only a cache: a missing file is empty and
+    /// unreadable lines are dropped.
+    pub fn load(path: impl AsRef<Path>) -> Self {
+        let src = std::fs::read_to_string(path).unwrap_or_default();
+        let profiles = src
+            .lines()
+            .filter_map(|line| serde_json::from_str::<Cached>(line).ok())
+            .map(|cached| (cached.profile.name.to_lowercase(), cached))
+            .collect();
+        Self { profiles }
+    }
+
+    /// Write every cached profile, replacing the file
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), DataFileError> {
+        let cached: Vec<&Cached> = self.profiles.values().collect();
+        write_lines(path.as_ref(), &cached)
+    }
+
+    /// A profile fetched less than `max_age` ago
+    pub fn get(&self, name: &str, max_age: Duration) -> Option<&Profile> {
+        let cached = self.profiles.get(&name.to_lowercase())?;
+        (Utc::now() - cached.fetched_at < max_age).then_some(&cached.profile)
+    }
+
+    /// Store a freshly fetched profile
+    pub fn insert(&mut self, profile: Profile) {
+        let cached = Cached { fetched_at: Utc::now(), profile };
+        self.profiles.insert(cached.profile.name.to_lowercase(), cached);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_stale_profiles_are_misses() {
+        let profile: Profile = serde_json::from_str(r#"{"id": "u1", "name": "Someone", "karma": 3}"#).unwrap();
+        let mut cache = ProfileCache::new();
+        cache.insert(profile);
+        assert_eq!(cache.get("someone", Duration::hours(1)).unwrap().karma, 3);
+        cache.profiles.get_mut("someone").unwrap().fetched_at -= Duration::hours(2);
+        assert!(cache.get("someone", Duration::hours(1)).is_none());
+    }
+}
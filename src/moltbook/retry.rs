@@ -0,0 +1,158 @@
+use reqwest::header::{HeaderMap, RETRY_AFTER};
+use reqwest::StatusCode;
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hasher};
+use std::time::Duration;
+
+use super::MoltbookError;
+
+/// When and how often [`MoltbookClient`](super::MoltbookClient) retries failed requests.
+///
+/// Rate limits (429), server errors (5xx), timeouts and connection failures are
+/// retried with exponential backoff. Only GET requests are retried unless
+/// `retry_writes` is set, since a write may have gone through before failing.
+#[derive(Debug, Clone)]
+pub struct RetryPolicy {
+    /// Total attempts including the first one
+    pub max_attempts: u32,
+    /// Delay before the first retry; doubles on every further retry
+    pub base_delay: Duration,
+    /// Upper bound for a single delay. A `Retry-After` longer than this ends retrying.
+    pub max_delay: Duration,
+    /// Randomize each delay between half and all of its nominal value
+    pub jitter: bool,
+    /// Also retry POST requests (posts, comments, votes)
+    pub retry_writes: bool,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 3,
+            base_delay: Duration::from_millis(500),
+            max_delay: Duration::from_secs(30),
+            jitter: true,
+            retry_writes: false,
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Never retry
+    pub fn none() -> Self {
+        Self { max_attempts: 1, ..Self::default() }
+    }
+
+    pub(crate) fn attempts_for(&self, write: bool) -> u32 {
+        if write && !self.retry_writes {
+            1
+        } else {
+            self.max_attempts.max(1)
+        }
+    }
+
+    /// Delay before retrying after `attempt` (1-based) failed, or `None` to give up
+    pub(crate) fn delay(&self, attempt: u32, retry_after: Option<Duration>) -> Option<Duration> {
+        if let Some(after) = retry_after {
+            return (after <= self.max_delay).then_some(after);
+        }
+
+        let nominal = self
+            .base_delay
+            .saturating_mul(2u32.saturating_pow(attempt - 1))
+            .min(self.max_delay);
+        if !self.jitter {
+            return Some(nominal);
+        }
+        let half = nominal / 2;
+        let random = RandomState::new().build_hasher().finish();
+        Some(half + half.mul_f64((random % 1000) as f64 / 1000.0))
+    }
+}
+
+/// A failed attempt that was retried
+#[derive(Debug)]
+pub struct Attempt {
+    pub error: MoltbookError,
+    /// How long the client waited before the next attempt
+    pub delay: Duration,
+}
+
+/// Whether a failure is worth another attempt
+pub(crate) fn is_retryable(error: &MoltbookError) -> bool {
+    match error {
+        MoltbookError::Transport(e) => e.is_connect(),
+        MoltbookError::Timeout(_) => true,
+        MoltbookError::RateLimited { .. } => true,
+        MoltbookError::Http { status, .. } => status.is_server_error(),
+        _ => false,
+    }
+}
+
+/// Parse a `Retry-After` header given in seconds or as an HTTP date
+pub(crate) fn retry_after(headers: &HeaderMap) -> Option<Duration> {
+    let value = headers.get(RETRY_AFTER)?.to_str().ok()?.trim();
+    if let Ok(secs) = value.parse() {
+        return Some(Duration::from_secs(secs));
+    }
+    let date = chrono::DateTime::parse_from_rfc2822(value).ok()?;
+    let wait = date.signed_duration_since(chrono::Utc::now());
+    Some(wait.to_std().unwrap_or(Duration::ZERO))
+}
+
+/// Statuses that get a `Retry-After` honored even when they aren't 429
+pub(crate) fn honors_retry_after(status: StatusCode) -> bool {
+    status == StatusCode::TOO_MANY_REQUESTS || status == StatusCode::SERVICE_UNAVAILABLE
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use reqwest::header::HeaderValue;
+
+    #[test]
+    fn test_exponential_backoff() {
+        let policy = RetryPolicy {
+            base_delay: Duration::from_millis(100),
+            max_delay: Duration::from_millis(350),
+            jitter: false,
+            ..RetryPolicy::default()
+        };
+        assert_eq!(policy.delay(1, None), Some(Duration::from_millis(100)));
+        assert_eq!(policy.delay(2, None), Some(Duration::from_millis(200)));
+        assert_eq!(policy.delay(3, None), Some(Duration::from_millis(350)));
+    }
+
+    #[test]
+    fn test_jitter_stays_in_range() {
+        let policy = RetryPolicy { base_delay: Duration::from_millis(100), ..RetryPolicy::default() };
+        for _ in 0..20 {
+            let delay = policy.delay(1, None).unwrap();
+            assert!(delay >= Duration::from_millis(50) && delay <= Duration::from_millis(100));
+        }
+    }
+
+    #[test]
+    fn test_retry_after_beyond_max_gives_up() {
+        let policy = RetryPolicy::default();
+        assert_eq!(policy.delay(1, Some(Duration::from_secs(2))), Some(Duration::from_secs(2)));
+        assert_eq!(policy.delay(1, Some(Duration::from_secs(3600))), None);
+    }
+
+    #[test]
+    fn test_writes_not_retried_by_default() {
+        let policy = RetryPolicy::default();
+        assert_eq!(policy.attempts_for(false), 3);
+        assert_eq!(policy.attempts_for(true), 1);
+    }
+
+    #[test]
+    fn test_parse_retry_after() {
+        let mut headers = HeaderMap::new();
+        headers.insert(RETRY_AFTER, HeaderValue::from_static("12"));
+        assert_eq!(retry_after(&headers), Some(Duration::from_secs(12)));
+
+        headers.insert(RETRY_AFTER, HeaderValue::from_static("Wed, 21 Oct 2015 07:28:00 GMT"));
+        assert_eq!(retry_after(&headers), Some(Duration::ZERO));
+    }
+}
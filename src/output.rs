@@ -0,0 +1,188 @@
+//! Machine-readable output for the `--format` flag
+
+use clap::ValueEnum;
+use colored::*;
+use serde::Serialize;
+use serde_json::{Map, Value};
+use std::sync::OnceLock;
+
+/// How commands print their results
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum)]
+pub enum Format {
+    /// Colored text for people
+    #[default]
+    Human,
+    /// One JSON document
+    Json,
+    /// One JSON object per line
+    Ndjson,
+    /// Comma-separated values with a header row
+    Csv,
+}
+
+static FORMAT: OnceLock<Format> = OnceLock::new();
+
+pub fn set_format(format: Format) {
+    let _ = FORMAT.set(format);
+}
+
+pub fn format() -> Format {
+    FORMAT.get().copied().unwrap_or_default()
+}
+
+pub fn is_human() -> bool {
+    format() == Format::Human
+}
+
+/// Print a progress message; silent in machine-readable formats
+pub fn status(message: impl std::fmt::Display) {
+    if is_human() {
+        println!("{}", message);
+    }
+}
+
+/// Print a single result object
+pub fn emit<T: Serialize>(value: &T) {
+    let value = serde_json::to_value(value).expect("output is serializable");
+    match format() {
+        Format::Human => {}
+        Format::Json => println!("{}", serde_json::to_string_pretty(&value).unwrap()),
+        Format::Ndjson => println!("{}", value),
+        Format::Csv => print_csv(&[value]),
+    }
+}
+
+/// Print a list of results: a JSON array, one line per item, or one row per item
+pub fn emit_list<T: Serialize>(items: &[T]) {
+    let values: Vec<Value> = items
+        .iter()
+        .map(|item| serde_json::to_value(item).expect("output is serializable"))
+        .collect();
+    match format() {
+        Format::Human => {}
+        Format::Json => println!("{}", serde_json::to_string_pretty(&values).unwrap()),
+        Format::Ndjson => {
+            for value in &values {
+                println!("{}", value);
+            }
+        }
+        Format::Csv => print_csv(&values),
+    }
+}
+
+/// Report an error on stderr, as JSON unless the format is human
+pub fn error(kind: &str, message: impl std::fmt::Display, exit_code: i32) {
+    if is_human() {
+        eprintln!("{} {}", "Error:".red(), message);
+    } else {
+        let value = serde_json::json!({
+            "error": message.to_string(),
+            "kind": kind,
+            "exit_code": exit_code,
+        });
+        eprintln!("{}", value);
+    }
+}
+
+/// Report a problem that doesn't stop the command on stderr, as JSON unless the format is human
+pub fn warn(kind: &str, message: impl std::fmt::Display) {
+    if is_human() {
+        eprintln!("{} {}", "Warning:".yellow(), message);
+    } else {
+        let value = serde_json::json!({
+            "warning": message.to_string(),
+            "kind": kind,
+        });
+        eprintln!("{}", value);
+    }
+}
+
+fn print_csv(values: &[Value]) {
+    let rows: Vec<Map<String, Value>> = values
+        .iter()
+        .map(|value| {
+            let mut row = Map::new();
+            flatten("", value, &mut row);
+            row
+        })
+        .collect();
+
+    let mut columns: Vec<&String> = Vec::new();
+    for row in &rows {
+        for key in row.keys() {
+            if !columns.contains(&key) {
+                columns.push(key);
+            }
+        }
+    }
+
+    let header: Vec<String> = columns.iter().map(|c| csv_field(c)).collect();
+    println!("{}", header.join(","));
+    for row in &rows {
+        let fields: Vec<String> = columns
+            .iter()
+            .map(|c| csv_field(&row.get(*c).map(cell).unwrap_or_default()))
+            .collect();
+        println!("{}", fields.join(","));
+    }
+}
+
+/// Flatten nested objects into dotted column names
+fn flatten(prefix: &str, value: &Value, row: &mut Map<String, Value>) {
+    match value {
+        Value::Object(map) => {
+            for (key, value) in map {
+                let name = if prefix.is_empty() { key.clone() } else { format!("{}.{}", prefix, key) };
+                flatten(&name, value, row);
+            }
+        }
+        other => {
+            let name = if prefix.is_empty() { "value".to_string() } else { prefix.to_string() };
+            row.insert(name, other.clone());
+        }
+    }
+}
+
+/// Text of a CSV cell; lists of scalars are joined with "; ", anything nested stays JSON
+fn cell(value: &Value) -> String {
+    match value {
+        Value::Null => String::new(),
+        Value::String(s) => s.clone(),
+        Value::Array(items) if items.iter().all(|v| !v.is_object() && !v.is_array()) => {
+            items.iter().map(cell).collect::<Vec<_>>().join("; ")
+        }
+        other => other.to_string(),
+    }
+}
+
+fn csv_field(text: &str) -> String {
+    if text.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", text.replace('"', "\"\""))
+    } else {
+        text.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_flatten_and_cells() {
+        let value = serde_json::json!({
+            "id": "p1",
+            "analysis": { "score": 40, "flags": ["a", "b"] },
+        });
+        let mut row = Map::new();
+        flatten("", &value, &mut row);
+        assert_eq!(cell(&row["analysis.score"]), "40");
+        assert_eq!(cell(&row["analysis.flags"]), "a; b");
+    }
+
+    #[test]
+    fn test_csv_quoting() {
+        assert_eq!(csv_field("plain"), "plain");
+        assert_eq!(csv_field("a, b"), "\"a, b\"");
+        assert_eq!(csv_field("say \"hi\""), "\"say \"\"hi\"\"\"");
+    }
+}
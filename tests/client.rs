@@ -0,0 +1,500 @@
+mod common;
+
+use common::{Response, StubServer};
+use chrono::{DateTime, Utc};
+use futures_util::TryStreamExt;
+use moltbook_filter::moltbook::{FeedQuery, FeedSource, MoltbookClient, MoltbookError, RetryPolicy};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use reqwest::header::{HeaderMap, HeaderValue};
+use serde_json::json;
+use std::time::Duration;
+
+fn client(server: &StubServer) -> MoltbookClient {
+    client_with_retry(server, RetryPolicy::none())
+}
+
+fn client_with_retry(server: &StubServer, retry: RetryPolicy) -> MoltbookClient {
+    MoltbookClient::builder()
+        .api_key("test-key")
+        .base_url(&server.base_url)
+        .retry_policy(retry)
+        .build()
+        .unwrap()
+}
+
+fn fast_retry() -> RetryPolicy {
+    RetryPolicy {
+        base_delay: Duration::from_millis(1),
+        jitter: false,
+        ..RetryPolicy::default()
+    }
+}
+
+/// Fails with `failure` the first `failures` times, then succeeds with `success`
+fn flaky(failures: usize, failure: Response, success: Response) -> StubServer {
+    let calls = Arc::new(AtomicUsize::new(0));
+    StubServer::start(move |_| {
+        if calls.fetch_add(1, Ordering::SeqCst) < failures {
+            failure.clone()
+        } else {
+            success.clone()
+        }
+    })
+}
+
+fn raw_post(id: &str, title: &str) -> serde_json::Value {
+    json!({
+        "id": id,
+        "title": title,
+        "content": "Some content",
+        "author": { "name": "agent" },
+        "submolt": { "name": "general" },
+        "upvotes": 3,
+        "comment_count": 1,
+        "created_at": "2026-01-01T00:00:00Z"
+    })
+}
+
+fn query_param(path: &str, name: &str) -> Option<String> {
+    let (_, query) = path.split_once('?')?;
+    query
+        .split('&')
+        .filter_map(|pair| pair.split_once('='))
+        .find(|(key, _)| *key == name)
+        .map(|(_, value)| value.to_string())
+}
+
+/// Serves `total` posts through `limit`/`offset`, newest first, one minute apart
+fn offset_feed(total: usize) -> StubServer {
+    StubServer::start(move |req| {
+        let limit: usize = query_param(&req.path, "limit").unwrap().parse().unwrap();
+        let offset: usize = query_param(&req.path, "offset").map_or(0, |o| o.parse().unwrap());
+        let posts: Vec<_> = (offset..total.min(offset + limit))
+            .map(|i| {
+                let mut post = raw_post(&format!("p{}", i), "Post");
+                let created = "2026-01-01T12:00:00Z".parse::<DateTime<Utc>>().unwrap()
+                    - chrono::Duration::minutes(i as i64);
+                post["created_at"] = json!(created.to_rfc3339());
+                post
+            })
+            .collect();
+        Response::json(json!({ "success": true, "posts": posts }))
+    })
+}
+
+fn raw_user(name: &str) -> serde_json::Value {
+    json!({ "id": "u1", "name": name, "karma": 42, "followers": 7, "following": 3 })
+}
+
+#[tokio::test]
+async fn get_feed() {
+    let server = StubServer::fixed(Response::json(json!({
+        "success": true,
+        "posts": [raw_post("p1", "First"), raw_post("p2", "Second")]
+    })));
+
+    let posts = client(&server).get_feed("hot", 2).await.unwrap();
+
+    assert_eq!(posts.len(), 2);
+    assert_eq!(posts[0].title, "First");
+    assert_eq!(posts[0].author.as_deref(), Some("agent"));
+    assert_eq!(posts[0].submolt.as_deref(), Some("general"));
+    let req = server.last_request();
+    assert_eq!(req.method, "GET");
+    assert_eq!(req.path, "/posts?sort=hot&limit=2");
+    assert_eq!(req.header("authorization"), Some("Bearer test-key"));
+}
+
+#[tokio::test]
+async fn get_personalized_feed() {
+    let server = StubServer::fixed(Response::json(json!({
+        "success": true,
+        "posts": [raw_post("p1", "Mine")]
+    })));
+
+    let posts = client(&server).get_personalized_feed("new", 5).await.unwrap();
+
+    assert_eq!(posts[0].id, "p1");
+    assert_eq!(server.last_request().path, "/feed?sort=new&limit=5");
+}
+
+#[tokio::test]
+async fn get_submolt_and_user_posts() {
+    let server = StubServer::fixed(Response::json(json!({
+        "success": true,
+        "posts": [raw_post("p1", "Listed")]
+    })));
+    let client = client(&server);
+
+    client.get_submolt_feed("tech", "hot", 10).await.unwrap();
+    client.get_user_posts("some_agent", "new", 5).await.unwrap();
+    client.get_submolt_feed("a b/c?", "new&x", 1).await.unwrap();
+
+    let paths: Vec<_> = server.requests().into_iter().map(|r| r.path).collect();
+    assert_eq!(
+        paths,
+        vec![
+            "/submolts/tech/feed?sort=hot&limit=10",
+            "/users/some_agent/posts?sort=new&limit=5",
+            "/submolts/a%20b%2Fc%3F/feed?sort=new%26x&limit=1",
+        ]
+    );
+}
+
+#[tokio::test]
+async fn feed_stream_reads_from_source() {
+    let server = StubServer::fixed(Response::json(json!({ "success": true, "posts": [] })));
+    let client = client(&server);
+
+    for (source, path) in [
+        (FeedSource::Global, "/posts"),
+        (FeedSource::Personal, "/feed"),
+        (FeedSource::Submolt("tech".to_string()), "/submolts/tech/feed"),
+        (FeedSource::User("bot".to_string()), "/users/bot/posts"),
+    ] {
+        let query = FeedQuery::new("new").source(source).limit(5);
+        let posts: Vec<_> = client.feed_stream(query).try_collect().await.unwrap();
+        assert!(posts.is_empty());
+        assert_eq!(server.last_request().path, format!("{}?sort=new&limit=5", path));
+    }
+}
+
+#[tokio::test]
+async fn get_post() {
+    let server = StubServer::fixed(Response::json(json!({
+        "success": true,
+        "post": raw_post("p1", "Hello")
+    })));
+
+    let post = client(&server).get_post("p1").await.unwrap();
+
+    assert_eq!(post.title, "Hello");
+    assert_eq!(post.upvotes, 3);
+    assert_eq!(server.last_request().path, "/posts/p1");
+}
+
+#[tokio::test]
+async fn create_post() {
+    let server = StubServer::start(|req| {
+        let body = req.json();
+        Response::json(json!({
+            "success": true,
+            "post": raw_post("new1", body["title"].as_str().unwrap())
+        }))
+    });
+
+    let post = client(&server)
+        .create_post("My title", "My content", Some("tech"))
+        .await
+        .unwrap();
+
+    assert_eq!(post.id, "new1");
+    assert_eq!(post.title, "My title");
+    let req = server.last_request();
+    assert_eq!(req.method, "POST");
+    assert_eq!(req.path, "/posts");
+    assert_eq!(
+        req.json(),
+        json!({ "title": "My title", "content": "My content", "submolt_name": "tech" })
+    );
+}
+
+#[tokio::test]
+async fn votes() {
+    let server = StubServer::fixed(Response::json(json!({ "success": true })));
+    let client = client(&server);
+
+    client.upvote("p1").await.unwrap();
+    client.downvote("p1").await.unwrap();
+    client.unvote("p1").await.unwrap();
+
+    let paths: Vec<_> = server.requests().into_iter().map(|r| (r.method, r.path)).collect();
+    assert_eq!(
+        paths,
+        vec![
+            ("POST".to_string(), "/posts/p1/upvote".to_string()),
+            ("POST".to_string(), "/posts/p1/downvote".to_string()),
+            ("POST".to_string(), "/posts/p1/unvote".to_string()),
+        ]
+    );
+}
+
+#[tokio::test]
+async fn comment() {
+    let server = StubServer::fixed(Response::json(json!({
+        "success": true,
+        "comment": { "id": "c1", "content": "Nice", "author": { "name": "me" } }
+    })));
+
+    let comment = client(&server).comment("p1", "Nice").await.unwrap();
+
+    assert_eq!(comment.id, "c1");
+    assert_eq!(comment.author.as_deref(), Some("me"));
+    let req = server.last_request();
+    assert_eq!((req.method.as_str(), req.path.as_str()), ("POST", "/posts/p1/comments"));
+    assert_eq!(req.json(), json!({ "content": "Nice" }));
+}
+
+#[tokio::test]
+async fn get_comments() {
+    let server = StubServer::fixed(Response::json(json!({
+        "success": true,
+        "comments": [
+            { "id": "c1", "content": "First", "upvotes": 2 },
+            { "id": "c2", "content": "Second", "author": { "name": "bot" } }
+        ]
+    })));
+
+    let comments = client(&server).get_comments("p1").await.unwrap();
+
+    assert_eq!(comments.len(), 2);
+    assert_eq!(comments[0].upvotes, 2);
+    assert_eq!(comments[1].author.as_deref(), Some("bot"));
+    assert_eq!(server.last_request().path, "/posts/p1/comments");
+}
+
+#[tokio::test]
+async fn profiles() {
+    let server = StubServer::start(|req| {
+        let name = if req.path == "/users/me" { "me" } else { "someone" };
+        Response::json(json!({ "success": true, "user": raw_user(name) }))
+    });
+    let client = client(&server);
+
+    let mine = client.get_my_profile().await.unwrap();
+    let theirs = client.get_profile("someone").await.unwrap();
+    // Names come from posts, so they can't reach another endpoint
+    client.get_profile("../me?x#y").await.unwrap();
+
+    assert_eq!(mine.name, "me");
+    assert_eq!(theirs.name, "someone");
+    assert_eq!(theirs.karma, 42);
+    let paths: Vec<_> = server.requests().into_iter().map(|r| r.path).collect();
+    assert_eq!(paths, vec!["/users/me", "/users/someone", "/users/..%2Fme%3Fx%23y"]);
+}
+
+#[tokio::test]
+async fn builder_settings_are_sent() {
+    let server = StubServer::fixed(Response::json(json!({ "success": true, "posts": [] })));
+    let mut headers = HeaderMap::new();
+    headers.insert("x-team", HeaderValue::from_static("spam-squad"));
+
+    let client = MoltbookClient::builder()
+        .api_key("k")
+        .base_url(format!("{}/", server.base_url))
+        .user_agent("custom-agent/1.0")
+        .default_headers(headers)
+        .timeout(Duration::from_secs(5))
+        .build()
+        .unwrap();
+    client.get_feed("new", 1).await.unwrap();
+
+    let req = server.last_request();
+    assert_eq!(req.path, "/posts?sort=new&limit=1");
+    assert_eq!(req.header("user-agent"), Some("custom-agent/1.0"));
+    assert_eq!(req.header("x-team"), Some("spam-squad"));
+}
+
+#[tokio::test]
+async fn error_statuses() {
+    let cases = [
+        (Response::status(401, "{}"), "unauthorized"),
+        (Response::status(403, "{}"), "unauthorized"),
+        (Response::status(404, "{}"), "not found"),
+        (Response::status(429, "{}").with_header("Retry-After", "7"), "rate limited"),
+        (Response::status(500, "boom"), "http"),
+    ];
+
+    for (response, expected) in cases {
+        let server = StubServer::fixed(response);
+        let err = client(&server).get_post("p1").await.unwrap_err();
+        match (expected, &err) {
+            ("unauthorized", MoltbookError::Unauthorized) => {}
+            ("not found", MoltbookError::NotFound) => {}
+            ("rate limited", MoltbookError::RateLimited { retry_after }) => {
+                assert_eq!(*retry_after, Some(Duration::from_secs(7)));
+            }
+            ("http", MoltbookError::Http { status, body }) => {
+                assert_eq!(status.as_u16(), 500);
+                assert_eq!(body, "boom");
+            }
+            _ => panic!("expected {}, got {:?}", expected, err),
+        }
+    }
+}
+
+#[tokio::test]
+async fn decode_and_api_errors() {
+    let server = StubServer::fixed(Response::status(200, "not json"));
+    let err = client(&server).get_feed("new", 1).await.unwrap_err();
+    assert!(matches!(err, MoltbookError::Decode(_)), "{:?}", err);
+
+    let server = StubServer::fixed(Response::json(json!({ "success": false, "error": "Nope" })));
+    let err = client(&server).get_feed("new", 1).await.unwrap_err();
+    assert!(matches!(&err, MoltbookError::Api { message } if message == "Nope"), "{:?}", err);
+}
+
+#[tokio::test]
+async fn transport_error() {
+    // Nothing listens on port 9 of localhost
+    let client = MoltbookClient::builder()
+        .base_url("http://127.0.0.1:9/api/v1")
+        .retry_policy(RetryPolicy::none())
+        .build()
+        .unwrap();
+    let err = client.get_feed("new", 1).await.unwrap_err();
+    assert!(matches!(err, MoltbookError::Transport(_)), "{:?}", err);
+}
+
+#[tokio::test]
+async fn timeout_error() {
+    let server = StubServer::start(|_| {
+        std::thread::sleep(Duration::from_millis(500));
+        Response::json(json!({ "success": true, "posts": [] }))
+    });
+    let client = MoltbookClient::builder()
+        .base_url(&server.base_url)
+        .timeout(Duration::from_millis(50))
+        .retry_policy(RetryPolicy::none())
+        .build()
+        .unwrap();
+    let err = client.get_feed("new", 1).await.unwrap_err();
+    assert!(matches!(err, MoltbookError::Timeout(_)), "{:?}", err);
+    assert!(err.is_timeout());
+}
+
+#[tokio::test]
+async fn retries_server_errors_on_get() {
+    let ok = Response::json(json!({ "success": true, "posts": [raw_post("p1", "Finally")] }));
+    let server = flaky(2, Response::status(503, "down"), ok);
+
+    let posts = client_with_retry(&server, fast_retry()).get_feed("new", 1).await.unwrap();
+
+    assert_eq!(posts[0].title, "Finally");
+    assert_eq!(server.requests().len(), 3);
+}
+
+#[tokio::test]
+async fn honors_retry_after() {
+    let limited = Response::status(429, "{}").with_header("Retry-After", "1");
+    let ok = Response::json(json!({ "success": true, "posts": [] }));
+    let server = flaky(1, limited, ok);
+
+    let started = std::time::Instant::now();
+    client_with_retry(&server, fast_retry()).get_feed("new", 1).await.unwrap();
+
+    assert!(started.elapsed() >= Duration::from_secs(1));
+    assert_eq!(server.requests().len(), 2);
+}
+
+#[tokio::test]
+async fn does_not_retry_client_errors() {
+    let server = StubServer::fixed(Response::status(404, "{}"));
+
+    let err = client_with_retry(&server, fast_retry()).get_post("p1").await.unwrap_err();
+
+    assert!(matches!(err, MoltbookError::NotFound), "{:?}", err);
+    assert_eq!(server.requests().len(), 1);
+}
+
+#[tokio::test]
+async fn writes_retry_only_when_allowed() {
+    let ok = Response::json(json!({ "success": true }));
+
+    let server = flaky(1, Response::status(502, "bad gateway"), ok.clone());
+    let err = client_with_retry(&server, fast_retry()).upvote("p1").await.unwrap_err();
+    assert!(matches!(err, MoltbookError::Http { .. }), "{:?}", err);
+    assert_eq!(server.requests().len(), 1);
+
+    let server = flaky(1, Response::status(502, "bad gateway"), ok);
+    let policy = RetryPolicy { retry_writes: true, ..fast_retry() };
+    client_with_retry(&server, policy).upvote("p1").await.unwrap();
+    assert_eq!(server.requests().len(), 2);
+}
+
+#[tokio::test]
+async fn exhausted_retries_report_every_attempt() {
+    let server = StubServer::fixed(Response::status(500, "boom"));
+
+    let err = client_with_retry(&server, fast_retry()).get_feed("new", 1).await.unwrap_err();
+
+    let MoltbookError::RetriesExhausted { attempts, last } = &err else {
+        panic!("expected RetriesExhausted, got {:?}", err);
+    };
+    assert_eq!(attempts.len(), 2);
+    assert!(matches!(**last, MoltbookError::Http { .. }));
+    assert!(matches!(err.root(), MoltbookError::Http { .. }));
+    assert!(err.to_string().contains("gave up after 3 attempts"));
+    assert_eq!(server.requests().len(), 3);
+}
+
+#[tokio::test]
+async fn feed_stream_follows_offsets_until_limit() {
+    let server = offset_feed(200);
+
+    let query = FeedQuery::new("new").limit(120).page_size(50);
+    let posts: Vec<_> = client(&server).feed_stream(query).try_collect().await.unwrap();
+
+    assert_eq!(posts.len(), 120);
+    assert_eq!(posts[119].id, "p119");
+    let offsets: Vec<_> = server
+        .requests()
+        .iter()
+        .map(|r| (query_param(&r.path, "offset"), query_param(&r.path, "limit").unwrap()))
+        .collect();
+    assert_eq!(
+        offsets,
+        vec![
+            (None, "50".to_string()),
+            (Some("50".to_string()), "50".to_string()),
+            (Some("100".to_string()), "20".to_string()),
+        ]
+    );
+}
+
+#[tokio::test]
+async fn feed_stream_stops_at_end_of_feed() {
+    let server = offset_feed(30);
+
+    let query = FeedQuery::new("hot").limit(500).page_size(20);
+    let posts: Vec<_> = client(&server).feed_stream(query).try_collect().await.unwrap();
+
+    assert_eq!(posts.len(), 30);
+    assert_eq!(server.requests().len(), 2);
+}
+
+#[tokio::test]
+async fn feed_stream_follows_cursors() {
+    let server = StubServer::start(|req| {
+        let (posts, next) = match query_param(&req.path, "cursor").as_deref() {
+            None => (vec![raw_post("a", "A"), raw_post("b", "B")], json!("c2&sort=x+y")),
+            // The cursor arrives encoded, not as extra parameters
+            Some("c2%26sort%3Dx%2By") => (vec![raw_post("c", "C")], json!(null)),
+            Some(other) => panic!("unexpected cursor {}", other),
+        };
+        Response::json(json!({ "success": true, "posts": posts, "next_cursor": next }))
+    });
+
+    let posts: Vec<_> = client(&server)
+        .feed_stream(FeedQuery::new("new").page_size(2))
+        .try_collect()
+        .await
+        .unwrap();
+
+    let ids: Vec<_> = posts.iter().map(|p| p.id.as_str()).collect();
+    assert_eq!(ids, vec!["a", "b", "c"]);
+}
+
+#[tokio::test]
+async fn feed_stream_stops_at_since_cutoff() {
+    let server = offset_feed(200);
+    let since = "2026-01-01T11:31:00Z".parse().unwrap();
+
+    let query = FeedQuery::new("new").since(since).page_size(10);
+    let posts: Vec<_> = client(&server).feed_stream(query).try_collect().await.unwrap();
+
+    // p0 is 12:00, p29 is 11:31; the page holding p30 ends the stream
+    assert_eq!(posts.len(), 30);
+    assert_eq!(server.requests().len(), 4);
+}